@@ -0,0 +1,753 @@
+use crate::stp258::{
+	BalanceStatus, LockIdentifier, Stp258Asset, Stp258AssetExtended, Stp258AssetLockable, Stp258AssetReservable,
+	Stp258Currency, Stp258CurrencyExtended, Stp258CurrencyLockable, Stp258CurrencyReservable,
+};
+use codec::FullCodec;
+use frame_support::traits::Get;
+use sp_runtime::{
+	traits::{MaybeSerializeDeserialize, Zero},
+	DispatchError, DispatchResult,
+};
+use sp_std::{
+	cmp::{Eq, PartialEq},
+	fmt::Debug,
+	marker::PhantomData,
+	result,
+};
+
+/// A `Stp258Asset` view of the currency `GetCurrencyId` of the
+/// `Stp258Currency` implementation `T`.
+pub struct Stp258AssetAdapter<T, GetCurrencyId>(PhantomData<(T, GetCurrencyId)>);
+
+impl<AccountId, T, GetCurrencyId> Stp258Asset<AccountId> for Stp258AssetAdapter<T, GetCurrencyId>
+where
+	T: Stp258Currency<AccountId>,
+	GetCurrencyId: Get<T::CurrencyId>,
+{
+	type Balance = T::Balance;
+
+	fn minimum_balance() -> Self::Balance {
+		T::minimum_balance(GetCurrencyId::get())
+	}
+
+	fn total_issuance() -> Self::Balance {
+		T::total_issuance(GetCurrencyId::get())
+	}
+
+	fn total_balance(who: &AccountId) -> Self::Balance {
+		T::total_balance(GetCurrencyId::get(), who)
+	}
+
+	fn free_balance(who: &AccountId) -> Self::Balance {
+		T::free_balance(GetCurrencyId::get(), who)
+	}
+
+	fn ensure_can_withdraw(who: &AccountId, amount: Self::Balance) -> DispatchResult {
+		T::ensure_can_withdraw(GetCurrencyId::get(), who, amount)
+	}
+
+	fn transfer(from: &AccountId, to: &AccountId, amount: Self::Balance) -> DispatchResult {
+		T::transfer(GetCurrencyId::get(), from, to, amount)
+	}
+
+	fn deposit(who: &AccountId, amount: Self::Balance) -> DispatchResult {
+		T::deposit(GetCurrencyId::get(), who, amount)
+	}
+
+	fn withdraw(who: &AccountId, amount: Self::Balance) -> DispatchResult {
+		T::withdraw(GetCurrencyId::get(), who, amount)
+	}
+
+	fn can_slash(who: &AccountId, value: Self::Balance) -> bool {
+		T::can_slash(GetCurrencyId::get(), who, value)
+	}
+
+	fn slash(who: &AccountId, amount: Self::Balance) -> Self::Balance {
+		T::slash(GetCurrencyId::get(), who, amount)
+	}
+}
+
+impl<AccountId, T, GetCurrencyId> Stp258AssetExtended<AccountId> for Stp258AssetAdapter<T, GetCurrencyId>
+where
+	T: Stp258CurrencyExtended<AccountId>,
+	GetCurrencyId: Get<T::CurrencyId>,
+{
+	type Amount = T::Amount;
+
+	fn update_balance(who: &AccountId, by_amount: Self::Amount) -> DispatchResult {
+		T::update_balance(GetCurrencyId::get(), who, by_amount)
+	}
+}
+
+impl<AccountId, T, GetCurrencyId> Stp258AssetLockable<AccountId> for Stp258AssetAdapter<T, GetCurrencyId>
+where
+	T: Stp258CurrencyLockable<AccountId>,
+	GetCurrencyId: Get<T::CurrencyId>,
+{
+	type Moment = T::Moment;
+
+	fn set_lock(lock_id: LockIdentifier, who: &AccountId, amount: Self::Balance) -> DispatchResult {
+		T::set_lock(lock_id, GetCurrencyId::get(), who, amount)
+	}
+
+	fn extend_lock(lock_id: LockIdentifier, who: &AccountId, amount: Self::Balance) -> DispatchResult {
+		T::extend_lock(lock_id, GetCurrencyId::get(), who, amount)
+	}
+
+	fn remove_lock(lock_id: LockIdentifier, who: &AccountId) -> DispatchResult {
+		T::remove_lock(lock_id, GetCurrencyId::get(), who)
+	}
+}
+
+impl<AccountId, T, GetCurrencyId> Stp258AssetReservable<AccountId> for Stp258AssetAdapter<T, GetCurrencyId>
+where
+	T: Stp258CurrencyReservable<AccountId>,
+	GetCurrencyId: Get<T::CurrencyId>,
+{
+	fn can_reserve(who: &AccountId, value: Self::Balance) -> bool {
+		T::can_reserve(GetCurrencyId::get(), who, value)
+	}
+
+	fn slash_reserved(who: &AccountId, value: Self::Balance) -> Self::Balance {
+		T::slash_reserved(GetCurrencyId::get(), who, value)
+	}
+
+	fn reserved_balance(who: &AccountId) -> Self::Balance {
+		T::reserved_balance(GetCurrencyId::get(), who)
+	}
+
+	fn reserve(who: &AccountId, value: Self::Balance) -> DispatchResult {
+		T::reserve(GetCurrencyId::get(), who, value)
+	}
+
+	fn unreserve(who: &AccountId, value: Self::Balance) -> Self::Balance {
+		T::unreserve(GetCurrencyId::get(), who, value)
+	}
+
+	fn repatriate_reserved(
+		slashed: &AccountId,
+		beneficiary: &AccountId,
+		value: Self::Balance,
+		status: BalanceStatus,
+	) -> result::Result<Self::Balance, DispatchError> {
+		T::repatriate_reserved(GetCurrencyId::get(), slashed, beneficiary, value, status)
+	}
+}
+
+/// A `Stp258Currency` holding the `Stp258Asset` implementation `T` under the
+/// currency `GetNativeCurrencyId`, with the base unit `GetBaseUnit`.
+///
+/// Any other currency id holds no balance: queries return zero, slashes and
+/// unreserves return the full value, and mutations fail with
+/// `InvalidCurrencyId`.
+pub struct Stp258NativeAdapter<T, CurrencyId, GetNativeCurrencyId, GetBaseUnit>(
+	PhantomData<(T, CurrencyId, GetNativeCurrencyId, GetBaseUnit)>,
+);
+
+impl<T, CurrencyId, GetNativeCurrencyId, GetBaseUnit>
+	Stp258NativeAdapter<T, CurrencyId, GetNativeCurrencyId, GetBaseUnit>
+where
+	CurrencyId: PartialEq,
+	GetNativeCurrencyId: Get<CurrencyId>,
+{
+	fn ensure_native(currency_id: CurrencyId) -> DispatchResult {
+		if currency_id == GetNativeCurrencyId::get() {
+			Ok(())
+		} else {
+			Err(DispatchError::Other("InvalidCurrencyId"))
+		}
+	}
+
+	fn is_native(currency_id: CurrencyId) -> bool {
+		Self::ensure_native(currency_id).is_ok()
+	}
+}
+
+impl<AccountId, T, CurrencyId, GetNativeCurrencyId, GetBaseUnit> Stp258Currency<AccountId>
+	for Stp258NativeAdapter<T, CurrencyId, GetNativeCurrencyId, GetBaseUnit>
+where
+	T: Stp258Asset<AccountId>,
+	CurrencyId: FullCodec + Eq + PartialEq + Copy + MaybeSerializeDeserialize + Debug,
+	GetNativeCurrencyId: Get<CurrencyId>,
+	GetBaseUnit: Get<T::Balance>,
+{
+	type CurrencyId = CurrencyId;
+	type Balance = T::Balance;
+
+	fn minimum_balance(currency_id: Self::CurrencyId) -> Self::Balance {
+		if Self::is_native(currency_id) {
+			T::minimum_balance()
+		} else {
+			Zero::zero()
+		}
+	}
+
+	fn base_unit(currency_id: Self::CurrencyId) -> Self::Balance {
+		if Self::is_native(currency_id) {
+			GetBaseUnit::get()
+		} else {
+			Zero::zero()
+		}
+	}
+
+	fn total_issuance(currency_id: Self::CurrencyId) -> Self::Balance {
+		if Self::is_native(currency_id) {
+			T::total_issuance()
+		} else {
+			Zero::zero()
+		}
+	}
+
+	fn total_balance(currency_id: Self::CurrencyId, who: &AccountId) -> Self::Balance {
+		if Self::is_native(currency_id) {
+			T::total_balance(who)
+		} else {
+			Zero::zero()
+		}
+	}
+
+	fn free_balance(currency_id: Self::CurrencyId, who: &AccountId) -> Self::Balance {
+		if Self::is_native(currency_id) {
+			T::free_balance(who)
+		} else {
+			Zero::zero()
+		}
+	}
+
+	fn ensure_can_withdraw(currency_id: Self::CurrencyId, who: &AccountId, amount: Self::Balance) -> DispatchResult {
+		Self::ensure_native(currency_id)?;
+		T::ensure_can_withdraw(who, amount)
+	}
+
+	fn transfer(
+		currency_id: Self::CurrencyId,
+		from: &AccountId,
+		to: &AccountId,
+		amount: Self::Balance,
+	) -> DispatchResult {
+		Self::ensure_native(currency_id)?;
+		T::transfer(from, to, amount)
+	}
+
+	fn deposit(currency_id: Self::CurrencyId, who: &AccountId, amount: Self::Balance) -> DispatchResult {
+		Self::ensure_native(currency_id)?;
+		T::deposit(who, amount)
+	}
+
+	fn withdraw(currency_id: Self::CurrencyId, who: &AccountId, amount: Self::Balance) -> DispatchResult {
+		Self::ensure_native(currency_id)?;
+		T::withdraw(who, amount)
+	}
+
+	fn can_slash(currency_id: Self::CurrencyId, who: &AccountId, value: Self::Balance) -> bool {
+		if Self::is_native(currency_id) {
+			T::can_slash(who, value)
+		} else {
+			value.is_zero()
+		}
+	}
+
+	fn slash(currency_id: Self::CurrencyId, who: &AccountId, amount: Self::Balance) -> Self::Balance {
+		if Self::is_native(currency_id) {
+			T::slash(who, amount)
+		} else {
+			amount
+		}
+	}
+}
+
+impl<AccountId, T, CurrencyId, GetNativeCurrencyId, GetBaseUnit> Stp258CurrencyExtended<AccountId>
+	for Stp258NativeAdapter<T, CurrencyId, GetNativeCurrencyId, GetBaseUnit>
+where
+	T: Stp258AssetExtended<AccountId>,
+	CurrencyId: FullCodec + Eq + PartialEq + Copy + MaybeSerializeDeserialize + Debug,
+	GetNativeCurrencyId: Get<CurrencyId>,
+	GetBaseUnit: Get<T::Balance>,
+{
+	type Amount = T::Amount;
+
+	fn update_balance(currency_id: Self::CurrencyId, who: &AccountId, by_amount: Self::Amount) -> DispatchResult {
+		Self::ensure_native(currency_id)?;
+		T::update_balance(who, by_amount)
+	}
+}
+
+impl<AccountId, T, CurrencyId, GetNativeCurrencyId, GetBaseUnit> Stp258CurrencyLockable<AccountId>
+	for Stp258NativeAdapter<T, CurrencyId, GetNativeCurrencyId, GetBaseUnit>
+where
+	T: Stp258AssetLockable<AccountId>,
+	CurrencyId: FullCodec + Eq + PartialEq + Copy + MaybeSerializeDeserialize + Debug,
+	GetNativeCurrencyId: Get<CurrencyId>,
+	GetBaseUnit: Get<T::Balance>,
+{
+	type Moment = T::Moment;
+
+	fn set_lock(
+		lock_id: LockIdentifier,
+		currency_id: Self::CurrencyId,
+		who: &AccountId,
+		amount: Self::Balance,
+	) -> DispatchResult {
+		Self::ensure_native(currency_id)?;
+		T::set_lock(lock_id, who, amount)
+	}
+
+	fn extend_lock(
+		lock_id: LockIdentifier,
+		currency_id: Self::CurrencyId,
+		who: &AccountId,
+		amount: Self::Balance,
+	) -> DispatchResult {
+		Self::ensure_native(currency_id)?;
+		T::extend_lock(lock_id, who, amount)
+	}
+
+	fn remove_lock(lock_id: LockIdentifier, currency_id: Self::CurrencyId, who: &AccountId) -> DispatchResult {
+		Self::ensure_native(currency_id)?;
+		T::remove_lock(lock_id, who)
+	}
+}
+
+impl<AccountId, T, CurrencyId, GetNativeCurrencyId, GetBaseUnit> Stp258CurrencyReservable<AccountId>
+	for Stp258NativeAdapter<T, CurrencyId, GetNativeCurrencyId, GetBaseUnit>
+where
+	T: Stp258AssetReservable<AccountId>,
+	CurrencyId: FullCodec + Eq + PartialEq + Copy + MaybeSerializeDeserialize + Debug,
+	GetNativeCurrencyId: Get<CurrencyId>,
+	GetBaseUnit: Get<T::Balance>,
+{
+	fn can_reserve(currency_id: Self::CurrencyId, who: &AccountId, value: Self::Balance) -> bool {
+		if Self::is_native(currency_id) {
+			T::can_reserve(who, value)
+		} else {
+			value.is_zero()
+		}
+	}
+
+	fn slash_reserved(currency_id: Self::CurrencyId, who: &AccountId, value: Self::Balance) -> Self::Balance {
+		if Self::is_native(currency_id) {
+			T::slash_reserved(who, value)
+		} else {
+			value
+		}
+	}
+
+	fn reserved_balance(currency_id: Self::CurrencyId, who: &AccountId) -> Self::Balance {
+		if Self::is_native(currency_id) {
+			T::reserved_balance(who)
+		} else {
+			Zero::zero()
+		}
+	}
+
+	fn reserve(currency_id: Self::CurrencyId, who: &AccountId, value: Self::Balance) -> DispatchResult {
+		Self::ensure_native(currency_id)?;
+		T::reserve(who, value)
+	}
+
+	fn unreserve(currency_id: Self::CurrencyId, who: &AccountId, value: Self::Balance) -> Self::Balance {
+		if Self::is_native(currency_id) {
+			T::unreserve(who, value)
+		} else {
+			value
+		}
+	}
+
+	fn repatriate_reserved(
+		currency_id: Self::CurrencyId,
+		slashed: &AccountId,
+		beneficiary: &AccountId,
+		value: Self::Balance,
+		status: BalanceStatus,
+	) -> result::Result<Self::Balance, DispatchError> {
+		Self::ensure_native(currency_id)?;
+		T::repatriate_reserved(slashed, beneficiary, value, status)
+	}
+}
+
+/// A `Stp258Currency` routing the currency `NativeId` to the `Stp258Asset`
+/// implementation `Native`, with the base unit `NativeBaseUnit`, and every
+/// other currency to the `Stp258Currency` implementation `Multi`.
+pub struct Stp258CurrencyRouter<Native, Multi, NativeId, NativeBaseUnit>(
+	PhantomData<(Native, Multi, NativeId, NativeBaseUnit)>,
+);
+
+impl<Native, Multi, NativeId, NativeBaseUnit> Stp258CurrencyRouter<Native, Multi, NativeId, NativeBaseUnit> {
+	fn is_native<CurrencyId: PartialEq>(currency_id: CurrencyId) -> bool
+	where
+		NativeId: Get<CurrencyId>,
+	{
+		currency_id == NativeId::get()
+	}
+}
+
+impl<AccountId, Native, Multi, NativeId, NativeBaseUnit> Stp258Currency<AccountId>
+	for Stp258CurrencyRouter<Native, Multi, NativeId, NativeBaseUnit>
+where
+	Native: Stp258Asset<AccountId, Balance = Multi::Balance>,
+	Multi: Stp258Currency<AccountId>,
+	NativeId: Get<Multi::CurrencyId>,
+	NativeBaseUnit: Get<Multi::Balance>,
+{
+	type CurrencyId = Multi::CurrencyId;
+	type Balance = Multi::Balance;
+
+	fn minimum_balance(currency_id: Self::CurrencyId) -> Self::Balance {
+		if Self::is_native(currency_id) {
+			Native::minimum_balance()
+		} else {
+			Multi::minimum_balance(currency_id)
+		}
+	}
+
+	fn base_unit(currency_id: Self::CurrencyId) -> Self::Balance {
+		if Self::is_native(currency_id) {
+			NativeBaseUnit::get()
+		} else {
+			Multi::base_unit(currency_id)
+		}
+	}
+
+	fn total_issuance(currency_id: Self::CurrencyId) -> Self::Balance {
+		if Self::is_native(currency_id) {
+			Native::total_issuance()
+		} else {
+			Multi::total_issuance(currency_id)
+		}
+	}
+
+	fn total_balance(currency_id: Self::CurrencyId, who: &AccountId) -> Self::Balance {
+		if Self::is_native(currency_id) {
+			Native::total_balance(who)
+		} else {
+			Multi::total_balance(currency_id, who)
+		}
+	}
+
+	fn free_balance(currency_id: Self::CurrencyId, who: &AccountId) -> Self::Balance {
+		if Self::is_native(currency_id) {
+			Native::free_balance(who)
+		} else {
+			Multi::free_balance(currency_id, who)
+		}
+	}
+
+	fn ensure_can_withdraw(currency_id: Self::CurrencyId, who: &AccountId, amount: Self::Balance) -> DispatchResult {
+		if Self::is_native(currency_id) {
+			Native::ensure_can_withdraw(who, amount)
+		} else {
+			Multi::ensure_can_withdraw(currency_id, who, amount)
+		}
+	}
+
+	fn transfer(
+		currency_id: Self::CurrencyId,
+		from: &AccountId,
+		to: &AccountId,
+		amount: Self::Balance,
+	) -> DispatchResult {
+		if Self::is_native(currency_id) {
+			Native::transfer(from, to, amount)
+		} else {
+			Multi::transfer(currency_id, from, to, amount)
+		}
+	}
+
+	fn deposit(currency_id: Self::CurrencyId, who: &AccountId, amount: Self::Balance) -> DispatchResult {
+		if Self::is_native(currency_id) {
+			Native::deposit(who, amount)
+		} else {
+			Multi::deposit(currency_id, who, amount)
+		}
+	}
+
+	fn withdraw(currency_id: Self::CurrencyId, who: &AccountId, amount: Self::Balance) -> DispatchResult {
+		if Self::is_native(currency_id) {
+			Native::withdraw(who, amount)
+		} else {
+			Multi::withdraw(currency_id, who, amount)
+		}
+	}
+
+	fn can_slash(currency_id: Self::CurrencyId, who: &AccountId, value: Self::Balance) -> bool {
+		if Self::is_native(currency_id) {
+			Native::can_slash(who, value)
+		} else {
+			Multi::can_slash(currency_id, who, value)
+		}
+	}
+
+	fn slash(currency_id: Self::CurrencyId, who: &AccountId, amount: Self::Balance) -> Self::Balance {
+		if Self::is_native(currency_id) {
+			Native::slash(who, amount)
+		} else {
+			Multi::slash(currency_id, who, amount)
+		}
+	}
+}
+
+impl<AccountId, Native, Multi, NativeId, NativeBaseUnit> Stp258CurrencyExtended<AccountId>
+	for Stp258CurrencyRouter<Native, Multi, NativeId, NativeBaseUnit>
+where
+	Native: Stp258AssetExtended<AccountId, Balance = Multi::Balance, Amount = Multi::Amount>,
+	Multi: Stp258CurrencyExtended<AccountId>,
+	NativeId: Get<Multi::CurrencyId>,
+	NativeBaseUnit: Get<Multi::Balance>,
+{
+	type Amount = Multi::Amount;
+
+	fn update_balance(currency_id: Self::CurrencyId, who: &AccountId, by_amount: Self::Amount) -> DispatchResult {
+		if Self::is_native(currency_id) {
+			Native::update_balance(who, by_amount)
+		} else {
+			Multi::update_balance(currency_id, who, by_amount)
+		}
+	}
+}
+
+impl<AccountId, Native, Multi, NativeId, NativeBaseUnit> Stp258CurrencyLockable<AccountId>
+	for Stp258CurrencyRouter<Native, Multi, NativeId, NativeBaseUnit>
+where
+	Native: Stp258AssetLockable<AccountId, Balance = Multi::Balance>,
+	Multi: Stp258CurrencyLockable<AccountId>,
+	NativeId: Get<Multi::CurrencyId>,
+	NativeBaseUnit: Get<Multi::Balance>,
+{
+	type Moment = Multi::Moment;
+
+	fn set_lock(
+		lock_id: LockIdentifier,
+		currency_id: Self::CurrencyId,
+		who: &AccountId,
+		amount: Self::Balance,
+	) -> DispatchResult {
+		if Self::is_native(currency_id) {
+			Native::set_lock(lock_id, who, amount)
+		} else {
+			Multi::set_lock(lock_id, currency_id, who, amount)
+		}
+	}
+
+	fn extend_lock(
+		lock_id: LockIdentifier,
+		currency_id: Self::CurrencyId,
+		who: &AccountId,
+		amount: Self::Balance,
+	) -> DispatchResult {
+		if Self::is_native(currency_id) {
+			Native::extend_lock(lock_id, who, amount)
+		} else {
+			Multi::extend_lock(lock_id, currency_id, who, amount)
+		}
+	}
+
+	fn remove_lock(lock_id: LockIdentifier, currency_id: Self::CurrencyId, who: &AccountId) -> DispatchResult {
+		if Self::is_native(currency_id) {
+			Native::remove_lock(lock_id, who)
+		} else {
+			Multi::remove_lock(lock_id, currency_id, who)
+		}
+	}
+}
+
+impl<AccountId, Native, Multi, NativeId, NativeBaseUnit> Stp258CurrencyReservable<AccountId>
+	for Stp258CurrencyRouter<Native, Multi, NativeId, NativeBaseUnit>
+where
+	Native: Stp258AssetReservable<AccountId, Balance = Multi::Balance>,
+	Multi: Stp258CurrencyReservable<AccountId>,
+	NativeId: Get<Multi::CurrencyId>,
+	NativeBaseUnit: Get<Multi::Balance>,
+{
+	fn can_reserve(currency_id: Self::CurrencyId, who: &AccountId, value: Self::Balance) -> bool {
+		if Self::is_native(currency_id) {
+			Native::can_reserve(who, value)
+		} else {
+			Multi::can_reserve(currency_id, who, value)
+		}
+	}
+
+	fn slash_reserved(currency_id: Self::CurrencyId, who: &AccountId, value: Self::Balance) -> Self::Balance {
+		if Self::is_native(currency_id) {
+			Native::slash_reserved(who, value)
+		} else {
+			Multi::slash_reserved(currency_id, who, value)
+		}
+	}
+
+	fn reserved_balance(currency_id: Self::CurrencyId, who: &AccountId) -> Self::Balance {
+		if Self::is_native(currency_id) {
+			Native::reserved_balance(who)
+		} else {
+			Multi::reserved_balance(currency_id, who)
+		}
+	}
+
+	fn reserve(currency_id: Self::CurrencyId, who: &AccountId, value: Self::Balance) -> DispatchResult {
+		if Self::is_native(currency_id) {
+			Native::reserve(who, value)
+		} else {
+			Multi::reserve(currency_id, who, value)
+		}
+	}
+
+	fn unreserve(currency_id: Self::CurrencyId, who: &AccountId, value: Self::Balance) -> Self::Balance {
+		if Self::is_native(currency_id) {
+			Native::unreserve(who, value)
+		} else {
+			Multi::unreserve(currency_id, who, value)
+		}
+	}
+
+	fn repatriate_reserved(
+		currency_id: Self::CurrencyId,
+		slashed: &AccountId,
+		beneficiary: &AccountId,
+		value: Self::Balance,
+		status: BalanceStatus,
+	) -> result::Result<Self::Balance, DispatchError> {
+		if Self::is_native(currency_id) {
+			Native::repatriate_reserved(slashed, beneficiary, value, status)
+		} else {
+			Multi::repatriate_reserved(currency_id, slashed, beneficiary, value, status)
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::parameter_type_with_key;
+	use frame_support::parameter_types;
+
+	const ALICE: u64 = 1;
+	const BOB: u64 = 2;
+	const SETT: u32 = 0;
+	const DNAR: u32 = 1;
+
+	parameter_type_with_key! {
+		pub ExistentialDeposits: |_currency_id: u32| -> u64 {
+			2
+		};
+	}
+
+	parameter_type_with_key! {
+		pub BaseUnits: |currency_id: u32| -> u64 {
+			match currency_id {
+				&SETT => 1_000,
+				_ => 100,
+			}
+		};
+	}
+
+	crate::create_mock_stp258_currency!(
+		Tokens,
+		u64,
+		u32,
+		u64,
+		i64,
+		u64,
+		ExistentialDeposits,
+		BaseUnits,
+		()
+	);
+
+	crate::create_mock_stp258_currency!(
+		NativeTokens,
+		u64,
+		u32,
+		u64,
+		i64,
+		u64,
+		ExistentialDeposits,
+		BaseUnits,
+		()
+	);
+
+	parameter_types! {
+		pub const GetSett: u32 = SETT;
+		pub const GetDnar: u32 = DNAR;
+		pub const SettBaseUnit: u64 = 1_000;
+		pub const DnarBaseUnit: u64 = 100;
+	}
+
+	type Sett = Stp258AssetAdapter<Tokens, GetSett>;
+	type NativeSett = Stp258NativeAdapter<Sett, u32, GetSett, SettBaseUnit>;
+	type Dnar = Stp258AssetAdapter<NativeTokens, GetDnar>;
+	type Currencies = Stp258CurrencyRouter<Dnar, Tokens, GetDnar, DnarBaseUnit>;
+
+	#[test]
+	fn asset_adapter_should_forward_to_currency_id() {
+		assert_eq!(Sett::deposit(&ALICE, 100), Ok(()));
+		assert_eq!(Tokens::free_balance(SETT, &ALICE), 100);
+		assert_eq!(Tokens::free_balance(DNAR, &ALICE), 0);
+		assert_eq!(Sett::minimum_balance(), 2);
+
+		assert_eq!(Sett::transfer(&ALICE, &BOB, 40), Ok(()));
+		assert_eq!(Sett::update_balance(&BOB, -10), Ok(()));
+		assert_eq!(Sett::reserve(&BOB, 10), Ok(()));
+		assert_eq!(Tokens::reserved_balance(SETT, &BOB), 10);
+		assert_eq!(Sett::total_balance(&BOB), 30);
+		assert_eq!(Sett::total_issuance(), 90);
+	}
+
+	#[test]
+	fn native_adapter_should_reject_other_currency_ids() {
+		assert_eq!(NativeSett::deposit(SETT, &ALICE, 100), Ok(()));
+		assert_eq!(NativeSett::free_balance(SETT, &ALICE), 100);
+		assert_eq!(NativeSett::base_unit(SETT), 1_000);
+
+		assert_eq!(NativeSett::free_balance(DNAR, &ALICE), 0);
+		assert_eq!(NativeSett::total_issuance(DNAR), 0);
+		assert_eq!(
+			NativeSett::deposit(DNAR, &ALICE, 100),
+			Err(DispatchError::Other("InvalidCurrencyId"))
+		);
+		assert_eq!(
+			NativeSett::transfer(DNAR, &ALICE, &BOB, 10),
+			Err(DispatchError::Other("InvalidCurrencyId"))
+		);
+		assert!(!NativeSett::can_slash(DNAR, &ALICE, 10));
+		assert_eq!(NativeSett::slash(DNAR, &ALICE, 10), 10);
+		assert_eq!(NativeSett::unreserve(DNAR, &ALICE, 10), 10);
+		assert_eq!(Tokens::free_balance(DNAR, &ALICE), 0);
+	}
+
+	#[test]
+	fn router_should_route_by_currency_id() {
+		assert_eq!(Currencies::deposit(DNAR, &ALICE, 100), Ok(()));
+		assert_eq!(Currencies::deposit(SETT, &ALICE, 50), Ok(()));
+		assert_eq!(NativeTokens::free_balance(DNAR, &ALICE), 100);
+		assert_eq!(Tokens::free_balance(DNAR, &ALICE), 0);
+		assert_eq!(Tokens::free_balance(SETT, &ALICE), 50);
+		assert_eq!(Currencies::base_unit(DNAR), 100);
+
+		assert_eq!(Currencies::update_balance(DNAR, &ALICE, -30), Ok(()));
+		assert_eq!(Currencies::reserve(DNAR, &ALICE, 20), Ok(()));
+		assert_eq!(Currencies::set_lock(*b"1       ", SETT, &ALICE, 50), Ok(()));
+		assert!(Currencies::transfer(SETT, &ALICE, &BOB, 10).is_err());
+		assert_eq!(Currencies::transfer(DNAR, &ALICE, &BOB, 10), Ok(()));
+
+		assert_eq!(NativeTokens::free_balance(DNAR, &ALICE), 40);
+		assert_eq!(NativeTokens::reserved_balance(DNAR, &ALICE), 20);
+		assert_eq!(Currencies::total_issuance(DNAR), 70);
+		assert_eq!(Currencies::total_issuance(SETT), 50);
+	}
+
+	mod native_adapter_conformance {
+		use super::NativeSett;
+
+		crate::stp258_currency_conformance_tests!(NativeSett, u64, super::SETT, super::ALICE, super::BOB);
+	}
+
+	mod router_native_conformance {
+		use super::Currencies;
+
+		crate::stp258_currency_conformance_tests!(Currencies, u64, super::DNAR, super::ALICE, super::BOB);
+	}
+
+	mod router_multi_conformance {
+		use super::Currencies;
+
+		crate::stp258_currency_conformance_tests!(Currencies, u64, super::SETT, super::ALICE, super::BOB);
+	}
+}
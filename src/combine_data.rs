@@ -0,0 +1,392 @@
+use crate::{
+	data_provider::{arithmetic_median_timestamped, weighted_median_by},
+	CombineData, DataFeeder, DataProvider, GetByKey, TimestampedValue,
+};
+use frame_support::traits::{Get, Time};
+use sp_runtime::{
+	traits::{CheckedAdd, CheckedDiv, CheckedSub, One, Saturating},
+	DispatchResult, FixedPointNumber, PerThing, Permill,
+};
+use sp_std::{marker::PhantomData, prelude::*};
+
+/// Combine data provided by operators, weighting each operator.
+///
+/// Unlike `CombineData`, each value comes with the operator that provided
+/// it, which is needed to look up its weight. See `CombinedFeeder`.
+pub trait CombineWeightedData<AccountId, Key, TimestampedValue> {
+	/// Combine data provided by operators, each value paired with the operator
+	/// that provided it
+	fn combine_weighted_data(
+		key: &Key,
+		values: Vec<(AccountId, TimestampedValue)>,
+		prev_value: Option<TimestampedValue>,
+	) -> Option<TimestampedValue>;
+}
+
+/// Whether a value with `timestamp` has not yet expired at `now`.
+fn is_fresh<Moment: Saturating + PartialOrd + Copy>(timestamp: Moment, now: Moment, expires_in: Moment) -> bool {
+	timestamp.saturating_add(expires_in) > now
+}
+
+/// Keep the values that have not expired.
+fn fresh<Value: Ord, Now: Time, ExpiresIn: Get<Now::Moment>>(
+	values: Vec<TimestampedValue<Value, Now::Moment>>,
+) -> Vec<TimestampedValue<Value, Now::Moment>> {
+	let (now, expires_in) = (Now::now(), ExpiresIn::get());
+	values
+		.into_iter()
+		.filter(|v| is_fresh(v.timestamp, now, expires_in))
+		.collect()
+}
+
+/// Combine the values that have not expired after `ExpiresIn` into their
+/// median, ordered by value only, averaging the two middle values for an even
+/// count. The timestamp is the oldest of the values, see
+/// `arithmetic_median_timestamped`.
+///
+/// Returns `prev_value` if fewer than `MinimumCount` values are left.
+pub struct MedianCombineData<Now, ExpiresIn, MinimumCount>(PhantomData<(Now, ExpiresIn, MinimumCount)>);
+
+impl<Key, Value, Now, ExpiresIn, MinimumCount> CombineData<Key, TimestampedValue<Value, Now::Moment>>
+	for MedianCombineData<Now, ExpiresIn, MinimumCount>
+where
+	Value: Ord + One + CheckedAdd + CheckedSub + CheckedDiv,
+	Now: Time,
+	ExpiresIn: Get<Now::Moment>,
+	MinimumCount: Get<u32>,
+{
+	fn combine_data(
+		_key: &Key,
+		values: Vec<TimestampedValue<Value, Now::Moment>>,
+		prev_value: Option<TimestampedValue<Value, Now::Moment>>,
+	) -> Option<TimestampedValue<Value, Now::Moment>> {
+		let values = fresh::<Value, Now, ExpiresIn>(values);
+		if values.len() < MinimumCount::get() as usize {
+			return prev_value;
+		}
+
+		arithmetic_median_timestamped(values).or(prev_value)
+	}
+}
+
+/// Combine the values that have not expired after `ExpiresIn` into their
+/// mean, once the `Trim` share of the lowest and of the highest values is
+/// dropped. The timestamp is the oldest of the values kept.
+///
+/// Returns `prev_value` if fewer than `MinimumCount` values are left, or if
+/// the mean overflows.
+pub struct TrimmedMeanCombineData<Now, ExpiresIn, MinimumCount, Trim>(
+	PhantomData<(Now, ExpiresIn, MinimumCount, Trim)>,
+);
+
+impl<Key, Value, Now, ExpiresIn, MinimumCount, Trim> CombineData<Key, TimestampedValue<Value, Now::Moment>>
+	for TrimmedMeanCombineData<Now, ExpiresIn, MinimumCount, Trim>
+where
+	Value: FixedPointNumber,
+	Now: Time,
+	ExpiresIn: Get<Now::Moment>,
+	MinimumCount: Get<u32>,
+	Trim: Get<Permill>,
+{
+	fn combine_data(
+		_key: &Key,
+		values: Vec<TimestampedValue<Value, Now::Moment>>,
+		prev_value: Option<TimestampedValue<Value, Now::Moment>>,
+	) -> Option<TimestampedValue<Value, Now::Moment>> {
+		let mut values = fresh::<Value, Now, ExpiresIn>(values);
+		values.sort_by(|a, b| a.value.cmp(&b.value));
+		let count = values.len();
+		if count == 0 || count < MinimumCount::get() as usize {
+			return prev_value;
+		}
+
+		// always keep at least one value
+		let trim = (Trim::get().mul_floor(count as u32) as usize).min((count - 1) / 2);
+		let kept = &values[trim..count - trim];
+
+		let mean = kept
+			.iter()
+			.try_fold(Value::zero(), |sum, v| sum.checked_add(&v.value))
+			.and_then(|sum| sum.checked_div(&Value::saturating_from_integer(kept.len() as u128)));
+		let timestamp = kept.iter().map(|v| v.timestamp).min();
+		match (mean, timestamp) {
+			(Some(value), Some(timestamp)) => Some(TimestampedValue { value, timestamp }),
+			_ => prev_value,
+		}
+	}
+}
+
+/// Combine the values that have not expired after `ExpiresIn` into their
+/// median weighted by the `Weights` of the operators that provided them,
+/// ordered by value only. With equal weights this is the upper median of the
+/// values, where `MedianCombineData` averages the two middle values for an
+/// even count. The timestamp is the oldest of the values.
+///
+/// Returns `prev_value` if fewer than `MinimumCount` values are left, or if
+/// they all have zero weight.
+pub struct WeightedMedianCombineData<Now, ExpiresIn, MinimumCount, Weights>(
+	PhantomData<(Now, ExpiresIn, MinimumCount, Weights)>,
+);
+
+impl<AccountId, Key, Value, Now, ExpiresIn, MinimumCount, Weights>
+	CombineWeightedData<AccountId, Key, TimestampedValue<Value, Now::Moment>>
+	for WeightedMedianCombineData<Now, ExpiresIn, MinimumCount, Weights>
+where
+	Value: Ord,
+	Now: Time,
+	ExpiresIn: Get<Now::Moment>,
+	MinimumCount: Get<u32>,
+	Weights: GetByKey<AccountId, u128>,
+{
+	fn combine_weighted_data(
+		_key: &Key,
+		values: Vec<(AccountId, TimestampedValue<Value, Now::Moment>)>,
+		prev_value: Option<TimestampedValue<Value, Now::Moment>>,
+	) -> Option<TimestampedValue<Value, Now::Moment>> {
+		let (now, expires_in) = (Now::now(), ExpiresIn::get());
+		let values: Vec<_> = values
+			.into_iter()
+			.filter(|(_, v)| is_fresh(v.timestamp, now, expires_in))
+			.map(|(who, v)| (v, Weights::get(&who)))
+			.collect();
+		if values.is_empty() || values.len() < MinimumCount::get() as usize {
+			return prev_value;
+		}
+
+		let timestamp = values.iter().map(|(v, _)| v.timestamp).min();
+		match (weighted_median_by(values, |a, b| a.value.cmp(&b.value)), timestamp) {
+			(Some(median), Some(timestamp)) => Some(TimestampedValue {
+				value: median.value,
+				timestamp,
+			}),
+			_ => prev_value,
+		}
+	}
+}
+
+/// Storage of the values fed to a `CombinedFeeder`.
+pub trait CombinedFeederStore<AccountId, Key, TimestampedValue> {
+	/// The latest value fed by each operator for `key`.
+	fn raw_values(key: &Key) -> Vec<(AccountId, TimestampedValue)>;
+	/// Replace the latest value fed by `who` for `key`.
+	fn insert_raw_value(who: &AccountId, key: &Key, value: TimestampedValue);
+	/// The combined value of `key`.
+	fn combined(key: &Key) -> Option<TimestampedValue>;
+	/// Replace the combined value of `key`.
+	fn insert_combined(key: &Key, value: TimestampedValue);
+}
+
+/// A `DataFeeder` keeping the latest value fed by each operator, stamped
+/// with `Now`, and combining them with `Combine` on every feed. The combined
+/// value is what `get` returns.
+///
+/// `Combine` is given the operator of each value, so that it can weigh them
+/// by stake, which `CombineData` cannot.
+pub struct CombinedFeeder<AccountId, Now, Store, Combine>(PhantomData<(AccountId, Now, Store, Combine)>);
+
+impl<Key, Value, AccountId, Now, Store, Combine> DataProvider<Key, Value>
+	for CombinedFeeder<AccountId, Now, Store, Combine>
+where
+	Value: Ord,
+	Now: Time,
+	Store: CombinedFeederStore<AccountId, Key, TimestampedValue<Value, Now::Moment>>,
+{
+	fn get(key: &Key) -> Option<Value> {
+		Store::combined(key).map(|v| v.value)
+	}
+}
+
+impl<Key, Value, AccountId, Now, Store, Combine> DataFeeder<Key, Value, AccountId>
+	for CombinedFeeder<AccountId, Now, Store, Combine>
+where
+	Value: Ord,
+	Now: Time,
+	Store: CombinedFeederStore<AccountId, Key, TimestampedValue<Value, Now::Moment>>,
+	Combine: CombineWeightedData<AccountId, Key, TimestampedValue<Value, Now::Moment>>,
+{
+	fn feed_value(who: AccountId, key: Key, value: Value) -> DispatchResult {
+		let timestamp = Now::now();
+		Store::insert_raw_value(&who, &key, TimestampedValue { value, timestamp });
+
+		if let Some(combined) = Combine::combine_weighted_data(&key, Store::raw_values(&key), Store::combined(&key)) {
+			Store::insert_combined(&key, combined);
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::parameter_type_with_key;
+	use frame_support::parameter_types;
+	use sp_runtime::FixedU128;
+	use std::{cell::RefCell, collections::BTreeMap};
+
+	thread_local! {
+		static RAW_VALUES: RefCell<BTreeMap<(u8, u64), TimestampedValue<u32, u64>>> = RefCell::new(BTreeMap::new());
+		static COMBINED: RefCell<BTreeMap<u8, TimestampedValue<u32, u64>>> = RefCell::new(BTreeMap::new());
+	}
+
+	pub struct MockTime;
+	impl Time for MockTime {
+		type Moment = u64;
+
+		fn now() -> u64 {
+			100
+		}
+	}
+
+	parameter_types! {
+		pub const ExpiresIn: u64 = 10;
+		pub const MinimumCount: u32 = 3;
+		pub const Trim: Permill = Permill::from_percent(25);
+	}
+
+	parameter_type_with_key! {
+		pub Stakes: |who: u64| -> u128 {
+			match who {
+				1 => 10,
+				2 => 0,
+				_ => 1,
+			}
+		};
+	}
+
+	type Median = MedianCombineData<MockTime, ExpiresIn, MinimumCount>;
+	type TrimmedMean = TrimmedMeanCombineData<MockTime, ExpiresIn, MinimumCount, Trim>;
+	type WeightedMedian = WeightedMedianCombineData<MockTime, ExpiresIn, MinimumCount, Stakes>;
+
+	pub struct MockCombinedStore;
+	impl CombinedFeederStore<u64, u8, TimestampedValue<u32, u64>> for MockCombinedStore {
+		fn raw_values(key: &u8) -> Vec<(u64, TimestampedValue<u32, u64>)> {
+			RAW_VALUES.with(|v| {
+				v.borrow()
+					.iter()
+					.filter(|((k, _), _)| k == key)
+					.map(|((_, who), value)| (*who, *value))
+					.collect()
+			})
+		}
+
+		fn insert_raw_value(who: &u64, key: &u8, value: TimestampedValue<u32, u64>) {
+			RAW_VALUES.with(|v| v.borrow_mut().insert((*key, *who), value));
+		}
+
+		fn combined(key: &u8) -> Option<TimestampedValue<u32, u64>> {
+			COMBINED.with(|v| v.borrow().get(key).copied())
+		}
+
+		fn insert_combined(key: &u8, value: TimestampedValue<u32, u64>) {
+			COMBINED.with(|v| v.borrow_mut().insert(*key, value));
+		}
+	}
+
+	type Combined = CombinedFeeder<u64, MockTime, MockCombinedStore, WeightedMedian>;
+
+	fn tv<Value: Ord>(value: Value, timestamp: u64) -> TimestampedValue<Value, u64> {
+		TimestampedValue { value, timestamp }
+	}
+
+	fn fixed(n: u128) -> FixedU128 {
+		FixedU128::saturating_from_integer(n)
+	}
+
+	#[test]
+	fn median_should_order_by_value_only() {
+		let values = vec![tv(5u32, 99), tv(1, 100), tv(9, 95), tv(7, 91)];
+		assert_eq!(Median::combine_data(&0, values, None), Some(tv(6, 91)));
+
+		let values = vec![tv(5u32, 99), tv(1, 91), tv(9, 100)];
+		assert_eq!(Median::combine_data(&0, values, None), Some(tv(5, 91)));
+	}
+
+	#[test]
+	fn median_should_drop_expired_values() {
+		let values = vec![tv(5u32, 99), tv(1, 90), tv(9, 95), tv(7, 91)];
+		assert_eq!(Median::combine_data(&0, values, None), Some(tv(7, 91)));
+	}
+
+	#[test]
+	fn median_should_keep_prev_value_without_quorum() {
+		let values = vec![tv(5u32, 99), tv(1, 90), tv(9, 95)];
+		assert_eq!(Median::combine_data(&0, values, Some(tv(3, 50))), Some(tv(3, 50)));
+		assert_eq!(Median::combine_data(&0, vec![], None::<TimestampedValue<u32, u64>>), None);
+	}
+
+	#[test]
+	fn trimmed_mean_should_drop_extremes() {
+		let values = vec![
+			tv(fixed(1), 99),
+			tv(fixed(2), 98),
+			tv(fixed(3), 97),
+			tv(fixed(1_000), 100),
+		];
+		assert_eq!(
+			TrimmedMean::combine_data(&0, values, None),
+			Some(tv(FixedU128::saturating_from_rational(5, 2), 97))
+		);
+
+		let values = vec![tv(fixed(1), 99), tv(fixed(2), 98), tv(fixed(6), 97)];
+		assert_eq!(TrimmedMean::combine_data(&0, values, None), Some(tv(fixed(3), 97)));
+	}
+
+	#[test]
+	fn trimmed_mean_should_keep_prev_value_without_quorum() {
+		let values = vec![tv(fixed(1), 99), tv(fixed(2), 80), tv(fixed(6), 97)];
+		assert_eq!(
+			TrimmedMean::combine_data(&0, values, Some(tv(fixed(4), 60))),
+			Some(tv(fixed(4), 60))
+		);
+	}
+
+	#[test]
+	fn weighted_median_should_follow_stake() {
+		let values = vec![(1u64, tv(9u32, 99)), (3, tv(1, 99)), (4, tv(2, 99)), (5, tv(3, 99))];
+		assert_eq!(WeightedMedian::combine_weighted_data(&0, values, None), Some(tv(9, 99)));
+
+		// equal weights give the upper median
+		let values = vec![(3u64, tv(9u32, 99)), (4, tv(1, 99)), (5, tv(2, 99)), (6, tv(3, 99))];
+		assert_eq!(WeightedMedian::combine_weighted_data(&0, values, None), Some(tv(3, 99)));
+	}
+
+	#[test]
+	fn weighted_median_should_take_oldest_timestamp() {
+		let values = vec![(1u64, tv(9u32, 99)), (3, tv(1, 95)), (4, tv(2, 92)), (5, tv(3, 80))];
+		assert_eq!(WeightedMedian::combine_weighted_data(&0, values, None), Some(tv(9, 92)));
+	}
+
+	#[test]
+	fn weighted_median_should_keep_prev_value_without_quorum() {
+		let values = vec![(1u64, tv(9u32, 99)), (3, tv(1, 80)), (4, tv(2, 99))];
+		assert_eq!(
+			WeightedMedian::combine_weighted_data(&0, values, Some(tv(5, 50))),
+			Some(tv(5, 50))
+		);
+
+		let values = vec![(2u64, tv(9u32, 99)), (2, tv(1, 99)), (2, tv(2, 99))];
+		assert_eq!(
+			WeightedMedian::combine_weighted_data(&0, values, Some(tv(5, 50))),
+			Some(tv(5, 50))
+		);
+	}
+
+	#[test]
+	fn combined_feeder_should_combine_by_stake() {
+		assert_eq!(Combined::feed_value(3, 0, 5), Ok(()));
+		assert_eq!(Combined::feed_value(4, 0, 7), Ok(()));
+		assert_eq!(Combined::get(&0), None);
+
+		assert_eq!(Combined::feed_value(5, 0, 6), Ok(()));
+		assert_eq!(Combined::get(&0), Some(6));
+		assert_eq!(MockCombinedStore::combined(&0), Some(tv(6, 100)));
+
+		assert_eq!(Combined::feed_value(1, 0, 3), Ok(()));
+		assert_eq!(Combined::get(&0), Some(3));
+
+		// a new value replaces the operator's previous one
+		assert_eq!(Combined::feed_value(1, 0, 9), Ok(()));
+		assert_eq!(Combined::get(&0), Some(9));
+		assert_eq!(Combined::get(&1), None);
+	}
+}
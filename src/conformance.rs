@@ -0,0 +1,531 @@
+//! Conformance checks for `Stp258Currency` implementations.
+//!
+//! Each check exercises one documented contract of the `Stp258Currency*`
+//! traits and panics if the implementation breaks it. Every check expects
+//! `who` and `other` to be distinct accounts holding no balance of
+//! `currency_id`, and funds them through `deposit`.
+//!
+//! `stp258_currency_conformance_tests!` generates a test for every check, and
+//! `stp258_currency_imbalanced_conformance_tests!` for every check of
+//! `Stp258CurrencyImbalanced`.
+
+use crate::stp258::{
+	BalanceStatus, LockIdentifier, Stp258Currency, Stp258CurrencyExtended, Stp258CurrencyImbalanced,
+	Stp258CurrencyLockable, Stp258CurrencyReservable,
+};
+use frame_support::traits::Imbalance;
+use sp_runtime::traits::{One, Saturating, Zero};
+use sp_std::convert::TryFrom;
+
+const CONFORMANCE_LOCK: LockIdentifier = *b"conform ";
+
+/// Run `f` as is, for implementations that need no externalities.
+pub fn run(f: impl FnOnce()) {
+	f()
+}
+
+/// An amount comfortably above the existential deposit of `currency_id`.
+fn unit<AccountId, T: Stp258Currency<AccountId>>(currency_id: T::CurrencyId) -> T::Balance {
+	T::minimum_balance(currency_id)
+		.max(One::one())
+		.saturating_mul(10u32.into())
+}
+
+fn times<Balance: Saturating + From<u32>>(amount: Balance, n: u32) -> Balance {
+	amount.saturating_mul(n.into())
+}
+
+fn ensure_empty<AccountId, T: Stp258Currency<AccountId>>(currency_id: T::CurrencyId, who: &AccountId) {
+	assert!(
+		T::total_balance(currency_id, who).is_zero(),
+		"conformance checks expect accounts without balance"
+	);
+}
+
+/// `deposit` and `withdraw` change the total issuance by the same amount as
+/// the balance.
+pub fn total_issuance_tracks_deposit_and_withdraw<AccountId, T: Stp258Currency<AccountId>>(
+	currency_id: T::CurrencyId,
+	who: &AccountId,
+) {
+	ensure_empty::<AccountId, T>(currency_id, who);
+	let unit = unit::<AccountId, T>(currency_id);
+	let issuance = T::total_issuance(currency_id);
+
+	assert_eq!(T::deposit(currency_id, who, times(unit, 3)), Ok(()));
+	assert_eq!(T::free_balance(currency_id, who), times(unit, 3));
+	assert_eq!(T::total_issuance(currency_id), issuance + times(unit, 3));
+
+	assert_eq!(T::withdraw(currency_id, who, unit), Ok(()));
+	assert_eq!(T::free_balance(currency_id, who), times(unit, 2));
+	assert_eq!(T::total_issuance(currency_id), issuance + times(unit, 2));
+
+	assert!(T::withdraw(currency_id, who, times(unit, 3)).is_err());
+	assert_eq!(T::free_balance(currency_id, who), times(unit, 2));
+	assert_eq!(T::total_issuance(currency_id), issuance + times(unit, 2));
+}
+
+/// `transfer` moves funds without changing the total issuance, and fails
+/// without moving funds when the free balance is short.
+pub fn transfer_preserves_total_issuance<AccountId, T: Stp258Currency<AccountId>>(
+	currency_id: T::CurrencyId,
+	who: &AccountId,
+	other: &AccountId,
+) {
+	ensure_empty::<AccountId, T>(currency_id, who);
+	ensure_empty::<AccountId, T>(currency_id, other);
+	let unit = unit::<AccountId, T>(currency_id);
+
+	assert_eq!(T::deposit(currency_id, who, times(unit, 2)), Ok(()));
+	let issuance = T::total_issuance(currency_id);
+
+	assert_eq!(T::transfer(currency_id, who, other, unit), Ok(()));
+	assert_eq!(T::free_balance(currency_id, who), unit);
+	assert_eq!(T::free_balance(currency_id, other), unit);
+	assert_eq!(T::total_issuance(currency_id), issuance);
+
+	assert!(T::ensure_can_withdraw(currency_id, who, times(unit, 2)).is_err());
+	assert!(T::transfer(currency_id, who, other, times(unit, 2)).is_err());
+	assert_eq!(T::free_balance(currency_id, who), unit);
+	assert_eq!(T::free_balance(currency_id, other), unit);
+	assert_eq!(T::total_issuance(currency_id), issuance);
+}
+
+/// `slash` deducts as much as possible and returns the unslashed remainder.
+pub fn slash_returns_unslashed_remainder<AccountId, T: Stp258Currency<AccountId>>(
+	currency_id: T::CurrencyId,
+	who: &AccountId,
+) {
+	ensure_empty::<AccountId, T>(currency_id, who);
+	let unit = unit::<AccountId, T>(currency_id);
+
+	assert_eq!(T::deposit(currency_id, who, times(unit, 3)), Ok(()));
+	let issuance = T::total_issuance(currency_id);
+
+	assert_eq!(T::slash(currency_id, who, unit), Zero::zero());
+	assert_eq!(T::free_balance(currency_id, who), times(unit, 2));
+	assert_eq!(T::slash(currency_id, who, times(unit, 3)), unit);
+	assert!(T::total_balance(currency_id, who).is_zero());
+	assert_eq!(T::total_issuance(currency_id), issuance - times(unit, 3));
+}
+
+/// `can_slash` is `true` exactly when `slash` would deduct the whole value
+/// from the free balance.
+pub fn can_slash_agrees_with_slash<AccountId, T: Stp258Currency<AccountId>>(
+	currency_id: T::CurrencyId,
+	who: &AccountId,
+) {
+	ensure_empty::<AccountId, T>(currency_id, who);
+	let unit = unit::<AccountId, T>(currency_id);
+
+	assert_eq!(T::deposit(currency_id, who, times(unit, 2)), Ok(()));
+
+	assert!(!T::can_slash(currency_id, who, times(unit, 2) + One::one()));
+	assert_eq!(T::free_balance(currency_id, who), times(unit, 2));
+	assert!(T::can_slash(currency_id, who, times(unit, 2)));
+	assert_eq!(T::slash(currency_id, who, times(unit, 2)), Zero::zero());
+}
+
+/// `reserve` fails without moving funds when the free balance is short, and
+/// `can_reserve` agrees with it.
+pub fn reserve_fails_without_moving_funds_when_free_is_short<AccountId, T: Stp258CurrencyReservable<AccountId>>(
+	currency_id: T::CurrencyId,
+	who: &AccountId,
+) {
+	ensure_empty::<AccountId, T>(currency_id, who);
+	let unit = unit::<AccountId, T>(currency_id);
+
+	assert_eq!(T::deposit(currency_id, who, times(unit, 2)), Ok(()));
+
+	assert!(!T::can_reserve(currency_id, who, times(unit, 3)));
+	assert!(T::reserve(currency_id, who, times(unit, 3)).is_err());
+	assert_eq!(T::free_balance(currency_id, who), times(unit, 2));
+	assert!(T::reserved_balance(currency_id, who).is_zero());
+
+	assert!(T::can_reserve(currency_id, who, unit));
+	assert_eq!(T::reserve(currency_id, who, unit), Ok(()));
+	assert_eq!(T::free_balance(currency_id, who), unit);
+	assert_eq!(T::reserved_balance(currency_id, who), unit);
+	assert_eq!(T::total_balance(currency_id, who), times(unit, 2));
+}
+
+/// `unreserve` never fails, moves as much as possible and returns the
+/// remainder.
+pub fn unreserve_never_fails<AccountId, T: Stp258CurrencyReservable<AccountId>>(
+	currency_id: T::CurrencyId,
+	who: &AccountId,
+) {
+	ensure_empty::<AccountId, T>(currency_id, who);
+	let unit = unit::<AccountId, T>(currency_id);
+
+	assert_eq!(T::unreserve(currency_id, who, unit), unit);
+
+	assert_eq!(T::deposit(currency_id, who, times(unit, 2)), Ok(()));
+	assert_eq!(T::reserve(currency_id, who, unit), Ok(()));
+	assert_eq!(T::unreserve(currency_id, who, times(unit, 3)), times(unit, 2));
+	assert_eq!(T::free_balance(currency_id, who), times(unit, 2));
+	assert!(T::reserved_balance(currency_id, who).is_zero());
+}
+
+/// `slash_reserved` deducts as much as possible from the reserved balance
+/// and returns the remainder.
+pub fn slash_reserved_returns_remainder<AccountId, T: Stp258CurrencyReservable<AccountId>>(
+	currency_id: T::CurrencyId,
+	who: &AccountId,
+) {
+	ensure_empty::<AccountId, T>(currency_id, who);
+	let unit = unit::<AccountId, T>(currency_id);
+
+	assert_eq!(T::deposit(currency_id, who, times(unit, 2)), Ok(()));
+	assert_eq!(T::reserve(currency_id, who, unit), Ok(()));
+	let issuance = T::total_issuance(currency_id);
+
+	assert_eq!(T::slash_reserved(currency_id, who, times(unit, 2)), unit);
+	assert!(T::reserved_balance(currency_id, who).is_zero());
+	assert_eq!(T::free_balance(currency_id, who), unit);
+	assert_eq!(T::total_issuance(currency_id), issuance - unit);
+}
+
+/// `repatriate_reserved` moves reserved funds into the free or reserved
+/// balance of the beneficiary and returns the remainder.
+pub fn repatriate_reserved_moves_funds<AccountId, T: Stp258CurrencyReservable<AccountId>>(
+	currency_id: T::CurrencyId,
+	who: &AccountId,
+	other: &AccountId,
+) {
+	ensure_empty::<AccountId, T>(currency_id, who);
+	ensure_empty::<AccountId, T>(currency_id, other);
+	let unit = unit::<AccountId, T>(currency_id);
+
+	assert_eq!(T::deposit(currency_id, who, times(unit, 3)), Ok(()));
+	assert_eq!(T::reserve(currency_id, who, times(unit, 2)), Ok(()));
+	assert_eq!(T::deposit(currency_id, other, unit), Ok(()));
+	let issuance = T::total_issuance(currency_id);
+
+	assert_eq!(
+		T::repatriate_reserved(currency_id, who, other, unit, BalanceStatus::Free),
+		Ok(Zero::zero())
+	);
+	assert_eq!(T::free_balance(currency_id, other), times(unit, 2));
+
+	assert_eq!(
+		T::repatriate_reserved(currency_id, who, other, times(unit, 2), BalanceStatus::Reserved),
+		Ok(unit)
+	);
+	assert_eq!(T::reserved_balance(currency_id, other), unit);
+	assert!(T::reserved_balance(currency_id, who).is_zero());
+	assert_eq!(T::free_balance(currency_id, who), unit);
+	assert_eq!(T::total_issuance(currency_id), issuance);
+}
+
+/// `update_balance` deposits positive amounts and withdraws negative ones.
+pub fn update_balance_matches_deposit_and_withdraw<AccountId, T: Stp258CurrencyExtended<AccountId>>(
+	currency_id: T::CurrencyId,
+	who: &AccountId,
+) {
+	ensure_empty::<AccountId, T>(currency_id, who);
+	let unit = unit::<AccountId, T>(currency_id);
+	let amount = match T::Amount::try_from(unit) {
+		Ok(amount) => amount,
+		Err(_) => panic!("conformance unit does not fit into `Amount`"),
+	};
+	let issuance = T::total_issuance(currency_id);
+
+	assert_eq!(T::update_balance(currency_id, who, amount + amount), Ok(()));
+	assert_eq!(T::free_balance(currency_id, who), times(unit, 2));
+	assert_eq!(T::total_issuance(currency_id), issuance + times(unit, 2));
+
+	assert_eq!(T::update_balance(currency_id, who, -amount), Ok(()));
+	assert_eq!(T::free_balance(currency_id, who), unit);
+	assert_eq!(T::total_issuance(currency_id), issuance + unit);
+
+	assert_eq!(T::update_balance(currency_id, who, Zero::zero()), Ok(()));
+	assert_eq!(T::free_balance(currency_id, who), unit);
+}
+
+/// Locks restrict withdrawals to the largest lock; `extend_lock` keeps the
+/// larger amount, `set_lock` replaces it and `remove_lock` lifts it.
+pub fn locks_restrict_withdrawals<AccountId, T: Stp258CurrencyLockable<AccountId>>(
+	currency_id: T::CurrencyId,
+	who: &AccountId,
+) {
+	ensure_empty::<AccountId, T>(currency_id, who);
+	let unit = unit::<AccountId, T>(currency_id);
+
+	assert_eq!(T::deposit(currency_id, who, times(unit, 2)), Ok(()));
+
+	assert_eq!(T::set_lock(CONFORMANCE_LOCK, currency_id, who, unit), Ok(()));
+	assert!(T::ensure_can_withdraw(currency_id, who, times(unit, 2)).is_err());
+	assert_eq!(T::ensure_can_withdraw(currency_id, who, unit), Ok(()));
+
+	assert_eq!(T::extend_lock(CONFORMANCE_LOCK, currency_id, who, times(unit, 2)), Ok(()));
+	assert!(T::ensure_can_withdraw(currency_id, who, unit).is_err());
+	assert_eq!(T::extend_lock(CONFORMANCE_LOCK, currency_id, who, unit), Ok(()));
+	assert!(T::ensure_can_withdraw(currency_id, who, unit).is_err());
+
+	assert_eq!(T::set_lock(CONFORMANCE_LOCK, currency_id, who, unit), Ok(()));
+	assert_eq!(T::ensure_can_withdraw(currency_id, who, unit), Ok(()));
+
+	assert_eq!(T::remove_lock(CONFORMANCE_LOCK, currency_id, who), Ok(()));
+	assert_eq!(T::ensure_can_withdraw(currency_id, who, times(unit, 2)), Ok(()));
+	assert_eq!(T::withdraw(currency_id, who, times(unit, 2)), Ok(()));
+}
+
+/// Resolving an issued imbalance credits the balance and the total issuance
+/// alike, and settling a burned one debits both alike. Dropping an imbalance
+/// undoes the issuance change it stands for.
+pub fn imbalances_keep_total_issuance_consistent<AccountId, T: Stp258CurrencyImbalanced<AccountId>>(
+	currency_id: T::CurrencyId,
+	who: &AccountId,
+) {
+	ensure_empty::<AccountId, T>(currency_id, who);
+	let unit = unit::<AccountId, T>(currency_id);
+	let issuance = T::total_issuance(currency_id);
+
+	T::resolve_creating(currency_id, who, T::issue(currency_id, times(unit, 3)));
+	assert_eq!(T::free_balance(currency_id, who), times(unit, 3));
+	assert_eq!(T::total_issuance(currency_id), issuance + times(unit, 3));
+
+	assert!(T::settle(currency_id, who, T::burn(currency_id, unit)).is_ok());
+	assert_eq!(T::free_balance(currency_id, who), times(unit, 2));
+	assert_eq!(T::total_issuance(currency_id), issuance + times(unit, 2));
+
+	drop(T::issue(currency_id, unit));
+	drop(T::burn(currency_id, unit));
+	drop(T::pair(currency_id, unit));
+	assert_eq!(T::total_issuance(currency_id), issuance + times(unit, 2));
+
+	let credit = T::deposit_creating(currency_id, who, unit);
+	assert_eq!(credit.peek(), unit);
+	assert_eq!(T::free_balance(currency_id, who), times(unit, 3));
+	drop(credit);
+	assert_eq!(T::total_issuance(currency_id), issuance + times(unit, 3));
+}
+
+/// `settle` and `resolve_into_existing` fail without moving funds, and hand
+/// the imbalance back.
+pub fn failed_resolution_returns_imbalance<AccountId, T: Stp258CurrencyImbalanced<AccountId>>(
+	currency_id: T::CurrencyId,
+	who: &AccountId,
+	other: &AccountId,
+) {
+	ensure_empty::<AccountId, T>(currency_id, who);
+	ensure_empty::<AccountId, T>(currency_id, other);
+	let unit = unit::<AccountId, T>(currency_id);
+
+	assert!(T::deposit_into_existing(currency_id, other, unit).is_err());
+	let issued = match T::resolve_into_existing(currency_id, other, T::issue(currency_id, unit)) {
+		Ok(()) => panic!("`resolve_into_existing` credited a dead account"),
+		Err(issued) => issued,
+	};
+	assert_eq!(issued.peek(), unit);
+	assert!(T::total_balance(currency_id, other).is_zero());
+
+	T::resolve_creating(currency_id, who, issued);
+	let issuance = T::total_issuance(currency_id);
+	let burned = match T::settle(currency_id, who, T::burn(currency_id, times(unit, 2))) {
+		Ok(()) => panic!("`settle` debited more than the free balance"),
+		Err(burned) => burned,
+	};
+	assert_eq!(burned.peek(), times(unit, 2));
+	assert_eq!(T::free_balance(currency_id, who), unit);
+	drop(burned);
+	assert_eq!(T::total_issuance(currency_id), issuance);
+}
+
+/// Generate a test for every conformance check.
+///
+/// `$currency` implements all four `Stp258Currency*` traits for
+/// `$account_id`, and `$who` and `$other` hold no balance of `$currency_id`.
+/// The optional `$execute_with` runs each check, for example inside
+/// externalities. Invoke it once per module, as the tests are named after the
+/// checks.
+///
+/// Example:
+/// ```ignore
+/// stp258_currency_conformance_tests!(
+///     Tokens, AccountId, CurrencyId::SETT, ALICE, BOB,
+///     |f| ExtBuilder::default().build().execute_with(f)
+/// );
+/// ```
+#[macro_export]
+macro_rules! stp258_currency_conformance_tests {
+	($currency:ty, $account_id:ty, $currency_id:expr, $who:expr, $other:expr) => {
+		$crate::stp258_currency_conformance_tests!(
+			$currency,
+			$account_id,
+			$currency_id,
+			$who,
+			$other,
+			$crate::conformance::run
+		);
+	};
+	($currency:ty, $account_id:ty, $currency_id:expr, $who:expr, $other:expr, $execute_with:expr) => {
+		#[test]
+		fn total_issuance_tracks_deposit_and_withdraw() {
+			($execute_with)(|| {
+				$crate::conformance::total_issuance_tracks_deposit_and_withdraw::<$account_id, $currency>(
+					$currency_id,
+					&$who,
+				)
+			});
+		}
+
+		#[test]
+		fn transfer_preserves_total_issuance() {
+			($execute_with)(|| {
+				$crate::conformance::transfer_preserves_total_issuance::<$account_id, $currency>(
+					$currency_id,
+					&$who,
+					&$other,
+				)
+			});
+		}
+
+		#[test]
+		fn slash_returns_unslashed_remainder() {
+			($execute_with)(|| {
+				$crate::conformance::slash_returns_unslashed_remainder::<$account_id, $currency>($currency_id, &$who)
+			});
+		}
+
+		#[test]
+		fn can_slash_agrees_with_slash() {
+			($execute_with)(|| {
+				$crate::conformance::can_slash_agrees_with_slash::<$account_id, $currency>($currency_id, &$who)
+			});
+		}
+
+		#[test]
+		fn reserve_fails_without_moving_funds_when_free_is_short() {
+			($execute_with)(|| {
+				$crate::conformance::reserve_fails_without_moving_funds_when_free_is_short::<$account_id, $currency>(
+					$currency_id,
+					&$who,
+				)
+			});
+		}
+
+		#[test]
+		fn unreserve_never_fails() {
+			($execute_with)(|| {
+				$crate::conformance::unreserve_never_fails::<$account_id, $currency>($currency_id, &$who)
+			});
+		}
+
+		#[test]
+		fn slash_reserved_returns_remainder() {
+			($execute_with)(|| {
+				$crate::conformance::slash_reserved_returns_remainder::<$account_id, $currency>($currency_id, &$who)
+			});
+		}
+
+		#[test]
+		fn repatriate_reserved_moves_funds() {
+			($execute_with)(|| {
+				$crate::conformance::repatriate_reserved_moves_funds::<$account_id, $currency>(
+					$currency_id,
+					&$who,
+					&$other,
+				)
+			});
+		}
+
+		#[test]
+		fn update_balance_matches_deposit_and_withdraw() {
+			($execute_with)(|| {
+				$crate::conformance::update_balance_matches_deposit_and_withdraw::<$account_id, $currency>(
+					$currency_id,
+					&$who,
+				)
+			});
+		}
+
+		#[test]
+		fn locks_restrict_withdrawals() {
+			($execute_with)(|| {
+				$crate::conformance::locks_restrict_withdrawals::<$account_id, $currency>($currency_id, &$who)
+			});
+		}
+	};
+}
+
+/// Generate a test for every conformance check of `Stp258CurrencyImbalanced`.
+///
+/// Takes the same arguments as `stp258_currency_conformance_tests!`, with
+/// `$currency` implementing `Stp258CurrencyImbalanced`.
+#[macro_export]
+macro_rules! stp258_currency_imbalanced_conformance_tests {
+	($currency:ty, $account_id:ty, $currency_id:expr, $who:expr, $other:expr) => {
+		$crate::stp258_currency_imbalanced_conformance_tests!(
+			$currency,
+			$account_id,
+			$currency_id,
+			$who,
+			$other,
+			$crate::conformance::run
+		);
+	};
+	($currency:ty, $account_id:ty, $currency_id:expr, $who:expr, $other:expr, $execute_with:expr) => {
+		#[test]
+		fn imbalances_keep_total_issuance_consistent() {
+			($execute_with)(|| {
+				$crate::conformance::imbalances_keep_total_issuance_consistent::<$account_id, $currency>(
+					$currency_id,
+					&$who,
+				)
+			});
+		}
+
+		#[test]
+		fn failed_resolution_returns_imbalance() {
+			($execute_with)(|| {
+				$crate::conformance::failed_resolution_returns_imbalance::<$account_id, $currency>(
+					$currency_id,
+					&$who,
+					&$other,
+				)
+			});
+		}
+	};
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::parameter_type_with_key;
+
+	parameter_type_with_key! {
+		pub ExistentialDeposits: |currency_id: u32| -> u64 {
+			match currency_id {
+				0 => 2,
+				_ => 0,
+			}
+		};
+	}
+
+	crate::create_mock_stp258_currency!(
+		Tokens,
+		u64,
+		u32,
+		u64,
+		i64,
+		u64,
+		ExistentialDeposits,
+		ExistentialDeposits,
+		()
+	);
+
+	mod with_existential_deposit {
+		use super::Tokens;
+
+		crate::stp258_currency_conformance_tests!(Tokens, u64, 0, 1, 2);
+		crate::stp258_currency_imbalanced_conformance_tests!(Tokens, u64, 0, 1, 2);
+	}
+
+	mod without_existential_deposit {
+		use super::Tokens;
+
+		crate::stp258_currency_conformance_tests!(Tokens, u64, 1, 1, 2);
+		crate::stp258_currency_imbalanced_conformance_tests!(Tokens, u64, 1, 1, 2);
+	}
+}
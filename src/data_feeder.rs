@@ -0,0 +1,215 @@
+use crate::{DataFeeder, DataProvider};
+use codec::{Decode, Encode};
+use sp_runtime::{DispatchError, DispatchResult, RuntimeDebug};
+use sp_std::marker::PhantomData;
+
+/// The set of operators allowed to feed values.
+pub trait OracleMembership<AccountId> {
+	/// Whether `who` may feed values.
+	fn is_member(who: &AccountId) -> bool;
+}
+
+/// How often each operator may feed a value for each key.
+///
+/// Counts are per block: the implementation is expected to reset them, for
+/// example by clearing its storage on finalize.
+pub trait FeedPolicy<AccountId, Key> {
+	/// The number of values `who` may feed for `key` in one block.
+	fn feed_limit(who: &AccountId, key: &Key) -> u32;
+	/// The number of values `who` fed for `key` in the current block.
+	fn feed_count(who: &AccountId, key: &Key) -> u32;
+	/// Record that `who` fed a value for `key` in the current block.
+	fn note_feed(who: &AccountId, key: &Key);
+}
+
+/// No limit.
+impl<AccountId, Key> FeedPolicy<AccountId, Key> for () {
+	fn feed_limit(_who: &AccountId, _key: &Key) -> u32 {
+		u32::max_value()
+	}
+
+	fn feed_count(_who: &AccountId, _key: &Key) -> u32 {
+		0
+	}
+
+	fn note_feed(_who: &AccountId, _key: &Key) {}
+}
+
+/// The reason a value was not fed.
+///
+/// Converted to `DispatchError::BadOrigin` for `NotMember`, and to
+/// `DispatchError::Other` for `RateLimited`, so that both stay distinct from
+/// the module errors of the inner feeder once encoded.
+#[derive(Encode, Decode, Clone, Copy, Eq, PartialEq, RuntimeDebug)]
+pub enum FeedError {
+	/// The operator is not a member.
+	NotMember,
+	/// The operator reached its limit for the key in this block.
+	RateLimited,
+}
+
+impl From<FeedError> for DispatchError {
+	fn from(e: FeedError) -> Self {
+		match e {
+			FeedError::NotMember => DispatchError::BadOrigin,
+			FeedError::RateLimited => DispatchError::Other("FeedRateLimited"),
+		}
+	}
+}
+
+/// A `DataFeeder` accepting values from `Members` only, at most as often as
+/// `Policy` allows, before delegating to `Inner`.
+pub struct GuardedFeeder<Inner, Members, Policy>(PhantomData<(Inner, Members, Policy)>);
+
+impl<Key, Value, Inner, Members, Policy> DataProvider<Key, Value> for GuardedFeeder<Inner, Members, Policy>
+where
+	Inner: DataProvider<Key, Value>,
+{
+	fn get(key: &Key) -> Option<Value> {
+		Inner::get(key)
+	}
+}
+
+impl<Key, Value, AccountId, Inner, Members, Policy> DataFeeder<Key, Value, AccountId>
+	for GuardedFeeder<Inner, Members, Policy>
+where
+	Key: Clone,
+	AccountId: Clone,
+	Inner: DataFeeder<Key, Value, AccountId>,
+	Members: OracleMembership<AccountId>,
+	Policy: FeedPolicy<AccountId, Key>,
+{
+	fn feed_value(who: AccountId, key: Key, value: Value) -> DispatchResult {
+		if !Members::is_member(&who) {
+			return Err(FeedError::NotMember.into());
+		}
+		if Policy::feed_count(&who, &key) >= Policy::feed_limit(&who, &key) {
+			return Err(FeedError::RateLimited.into());
+		}
+
+		Inner::feed_value(who.clone(), key.clone(), value)?;
+		Policy::note_feed(&who, &key);
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::{cell::RefCell, collections::BTreeMap};
+
+	thread_local! {
+		static VALUES: RefCell<BTreeMap<u8, u32>> = RefCell::new(BTreeMap::new());
+		static FEEDS: RefCell<BTreeMap<(u64, u8), u32>> = RefCell::new(BTreeMap::new());
+	}
+
+	const ZERO_VALUE: DispatchError = DispatchError::Module {
+		index: 1,
+		error: 0,
+		message: Some("ZeroValue"),
+	};
+
+	pub struct MockFeeder;
+	impl DataProvider<u8, u32> for MockFeeder {
+		fn get(key: &u8) -> Option<u32> {
+			VALUES.with(|v| v.borrow().get(key).copied())
+		}
+	}
+	impl DataFeeder<u8, u32, u64> for MockFeeder {
+		fn feed_value(_who: u64, key: u8, value: u32) -> DispatchResult {
+			if value == 0 {
+				return Err(ZERO_VALUE);
+			}
+			VALUES.with(|v| v.borrow_mut().insert(key, value));
+			Ok(())
+		}
+	}
+
+	pub struct MockMembers;
+	impl OracleMembership<u64> for MockMembers {
+		fn is_member(who: &u64) -> bool {
+			*who < 10
+		}
+	}
+
+	pub struct MockPolicy;
+	impl FeedPolicy<u64, u8> for MockPolicy {
+		fn feed_limit(who: &u64, _key: &u8) -> u32 {
+			if *who == 1 {
+				2
+			} else {
+				1
+			}
+		}
+
+		fn feed_count(who: &u64, key: &u8) -> u32 {
+			FEEDS.with(|v| v.borrow().get(&(*who, *key)).copied().unwrap_or_default())
+		}
+
+		fn note_feed(who: &u64, key: &u8) {
+			FEEDS.with(|v| *v.borrow_mut().entry((*who, *key)).or_default() += 1);
+		}
+	}
+
+	fn new_block() {
+		FEEDS.with(|v| v.borrow_mut().clear());
+	}
+
+	type Feeder = GuardedFeeder<MockFeeder, MockMembers, MockPolicy>;
+	type UnlimitedFeeder = GuardedFeeder<MockFeeder, MockMembers, ()>;
+
+	#[test]
+	fn feed_value_should_reject_non_members() {
+		assert_eq!(
+			Feeder::feed_value(10, 0, 5),
+			Err(DispatchError::BadOrigin)
+		);
+		assert_eq!(Feeder::get(&0), None);
+	}
+
+	#[test]
+	fn feed_value_should_rate_limit_per_operator_and_key() {
+		assert_eq!(Feeder::feed_value(1, 0, 5), Ok(()));
+		assert_eq!(Feeder::feed_value(1, 0, 6), Ok(()));
+		assert_eq!(Feeder::feed_value(1, 0, 7), Err(DispatchError::Other("FeedRateLimited")));
+		assert_eq!(Feeder::get(&0), Some(6));
+
+		assert_eq!(Feeder::feed_value(2, 0, 8), Ok(()));
+		assert_eq!(Feeder::feed_value(2, 0, 9), Err(DispatchError::Other("FeedRateLimited")));
+		assert_eq!(Feeder::feed_value(2, 1, 9), Ok(()));
+
+		new_block();
+		assert_eq!(Feeder::feed_value(2, 0, 10), Ok(()));
+		assert_eq!(Feeder::get(&0), Some(10));
+	}
+
+	#[test]
+	fn feed_value_should_not_count_failed_feeds() {
+		assert_eq!(Feeder::feed_value(2, 0, 0), Err(ZERO_VALUE));
+		assert_eq!(MockPolicy::feed_count(&2, &0), 0);
+		assert_eq!(Feeder::feed_value(2, 0, 5), Ok(()));
+	}
+
+	#[test]
+	fn feed_errors_should_encode_distinctly() {
+		let errors = vec![
+			DispatchError::from(FeedError::NotMember).encode(),
+			DispatchError::from(FeedError::RateLimited).encode(),
+			ZERO_VALUE.encode(),
+		];
+		assert_ne!(errors[0], errors[1]);
+		assert_ne!(errors[0], errors[2]);
+		assert_ne!(errors[1], errors[2]);
+	}
+
+	#[test]
+	fn unit_policy_should_not_limit() {
+		for value in 1..10 {
+			assert_eq!(UnlimitedFeeder::feed_value(1, 0, value), Ok(()));
+		}
+		assert_eq!(
+			UnlimitedFeeder::feed_value(10, 0, 5),
+			Err(DispatchError::BadOrigin)
+		);
+	}
+}
@@ -1,5 +1,10 @@
-use sp_runtime::DispatchResult;
-use sp_std::vec::Vec;
+use crate::TimestampedValue;
+use frame_support::traits::Get;
+use sp_runtime::{
+	traits::{CheckedAdd, CheckedDiv, CheckedSub, One},
+	DispatchResult, FixedPointNumber, FixedU128, PerThing, Permill,
+};
+use sp_std::{cmp::Ordering, vec::Vec};
 
 /// Data provider with ability to provide data with no-op, and provide all data.
 pub trait DataFeeder<Key, Value, AccountId>: DataProvider<Key, Value> {
@@ -22,20 +27,224 @@ pub trait DataProviderExtended<Key, TimestampedValue> {
 	fn get_all_values() -> Vec<(Key, Option<TimestampedValue>)>;
 }
 
-#[allow(dead_code)] // rust cannot detect usage in macro_rules
-pub fn median<T: Ord + Clone>(mut items: Vec<T>) -> Option<T> {
+/// The upper median of `items`, see `upper_median`. Use `arithmetic_median`
+/// to average the two middle items of an even count.
+pub fn median<T: Ord>(items: Vec<T>) -> Option<T> {
+	upper_median(items)
+}
+
+/// The middle item of `items`, or the greater of the two middle items for an
+/// even count.
+pub fn upper_median<T: Ord>(mut items: Vec<T>) -> Option<T> {
+	if items.is_empty() {
+		return None;
+	}
+
+	let mid_index = items.len() / 2;
+	items.select_nth_unstable(mid_index);
+	Some(items.swap_remove(mid_index))
+}
+
+/// The middle item of `items`, or the lesser of the two middle items for an
+/// even count.
+pub fn lower_median<T: Ord>(mut items: Vec<T>) -> Option<T> {
+	if items.is_empty() {
+		return None;
+	}
+
+	let mid_index = (items.len() - 1) / 2;
+	items.select_nth_unstable(mid_index);
+	Some(items.swap_remove(mid_index))
+}
+
+/// The middle item of `items`, or the average of the two middle items for an
+/// even count, rounded down.
+///
+/// Returns `None` if `items` is empty or the average overflows.
+pub fn arithmetic_median<T>(mut items: Vec<T>) -> Option<T>
+where
+	T: Ord + One + CheckedAdd + CheckedSub + CheckedDiv,
+{
+	if items.len() % 2 == 1 {
+		return upper_median(items);
+	}
 	if items.is_empty() {
 		return None;
 	}
 
 	let mid_index = items.len() / 2;
+	items.select_nth_unstable(mid_index);
+	let upper = items.swap_remove(mid_index);
+	// the items left of `mid_index` are no greater than `upper`
+	let lower = items.into_iter().take(mid_index).max()?;
+	let two = T::one().checked_add(&T::one())?;
+	lower.checked_add(&upper.checked_sub(&lower)?.checked_div(&two)?)
+}
+
+/// The upper median of `items` ordered by value only. The timestamp is the
+/// oldest of `items`, so freshness checks on the result are conservative.
+pub fn median_timestamped<Value: Ord, Moment: Ord + Clone>(
+	mut items: Vec<TimestampedValue<Value, Moment>>,
+) -> Option<TimestampedValue<Value, Moment>> {
+	let timestamp = items.iter().map(|v| v.timestamp.clone()).min()?;
+
+	let mid_index = items.len() / 2;
+	items.select_nth_unstable_by(mid_index, |a, b| a.value.cmp(&b.value));
+	Some(TimestampedValue {
+		value: items.swap_remove(mid_index).value,
+		timestamp,
+	})
+}
+
+/// The arithmetic median of `items` ordered by value only, see
+/// `arithmetic_median`. The timestamp is the oldest of `items`, as for
+/// `median_timestamped`.
+pub fn arithmetic_median_timestamped<Value, Moment>(
+	items: Vec<TimestampedValue<Value, Moment>>,
+) -> Option<TimestampedValue<Value, Moment>>
+where
+	Value: Ord + One + CheckedAdd + CheckedSub + CheckedDiv,
+	Moment: Ord + Clone,
+{
+	let timestamp = items.iter().map(|v| v.timestamp.clone()).min()?;
+	let value = arithmetic_median(items.into_iter().map(|v| v.value).collect())?;
+	Some(TimestampedValue { value, timestamp })
+}
+
+/// How far a value is from a reference value, relative to the reference.
+pub trait RelativeDeviation {
+	/// Whether `self` is within `max_deviation` of `reference`.
+	fn is_within(&self, reference: &Self, max_deviation: Permill) -> bool;
+}
+
+fn is_within_u128(value: u128, reference: u128, max_deviation: Permill) -> bool {
+	let deviation = if value > reference {
+		value - reference
+	} else {
+		reference - value
+	};
+	deviation <= max_deviation.mul_floor(reference)
+}
+
+macro_rules! impl_relative_deviation {
+	($($t:ty),*) => {
+		$(
+			impl RelativeDeviation for $t {
+				fn is_within(&self, reference: &Self, max_deviation: Permill) -> bool {
+					is_within_u128(*self as u128, *reference as u128, max_deviation)
+				}
+			}
+		)*
+	};
+}
+
+impl_relative_deviation!(u8, u16, u32, u64, u128);
+
+impl RelativeDeviation for FixedU128 {
+	fn is_within(&self, reference: &Self, max_deviation: Permill) -> bool {
+		is_within_u128(self.into_inner(), reference.into_inner(), max_deviation)
+	}
+}
+
+impl<Value: Ord + PartialOrd + RelativeDeviation, Moment> RelativeDeviation for TimestampedValue<Value, Moment> {
+	fn is_within(&self, reference: &Self, max_deviation: Permill) -> bool {
+		self.value.is_within(&reference.value, max_deviation)
+	}
+}
+
+/// The weighted median of `items` ordered by `compare`: the first item at
+/// which the cumulative weight exceeds half of the total weight. With equal
+/// weights this is the same item as `upper_median`.
+///
+/// Returns `None` if `items` is empty or all weights are zero.
+pub fn weighted_median_by<T, F>(mut items: Vec<(T, u128)>, mut compare: F) -> Option<T>
+where
+	F: FnMut(&T, &T) -> Ordering,
+{
+	items.sort_by(|(a, _), (b, _)| compare(a, b));
+
+	let total = items.iter().fold(0u128, |total, (_, weight)| total.saturating_add(*weight));
+	let mut cumulative = 0u128;
+	for (item, weight) in items {
+		cumulative = cumulative.saturating_add(weight);
+		if cumulative.saturating_mul(2) > total {
+			return Some(item);
+		}
+	}
+	None
+}
+
+/// The weighted median of `items`, see `weighted_median_by`.
+pub fn weighted_median<T: Ord>(items: Vec<(T, u128)>) -> Option<T> {
+	weighted_median_by(items, Ord::cmp)
+}
 
-	items.sort();
+/// The weighted median of `items` once the outliers are dropped.
+///
+/// Items further than `MaxDeviation` from the weighted median of all items
+/// are dropped. Returns `None` if fewer than `MinResponders` items are
+/// given, or left after dropping the outliers.
+pub fn quorum_weighted_median<T, MinResponders, MaxDeviation>(items: Vec<(T, u128)>) -> Option<T>
+where
+	T: Ord + Clone + RelativeDeviation,
+	MinResponders: Get<u32>,
+	MaxDeviation: Get<Permill>,
+{
+	let items = drop_outliers::<_, MinResponders, MaxDeviation, _>(items, Ord::cmp)?;
+	weighted_median(items)
+}
 
-	// Won't panic as guarded items not empty case.
-	Some(items[mid_index as usize].clone())
+/// Like `quorum_weighted_median`, but ordered by value only. The timestamp
+/// is the oldest of the items left after dropping the outliers.
+pub fn quorum_weighted_median_timestamped<Value, Moment, MinResponders, MaxDeviation>(
+	items: Vec<(TimestampedValue<Value, Moment>, u128)>,
+) -> Option<TimestampedValue<Value, Moment>>
+where
+	Value: Ord + Clone + RelativeDeviation,
+	Moment: Ord + Clone,
+	MinResponders: Get<u32>,
+	MaxDeviation: Get<Permill>,
+{
+	let by_value = |a: &TimestampedValue<Value, Moment>, b: &TimestampedValue<Value, Moment>| a.value.cmp(&b.value);
+	let items = drop_outliers::<_, MinResponders, MaxDeviation, _>(items, by_value)?;
+	let timestamp = items.iter().map(|(v, _)| v.timestamp.clone()).min()?;
+	weighted_median_by(items, by_value).map(|median| TimestampedValue {
+		value: median.value,
+		timestamp,
+	})
 }
 
+fn drop_outliers<T, MinResponders, MaxDeviation, F>(items: Vec<(T, u128)>, mut compare: F) -> Option<Vec<(T, u128)>>
+where
+	T: Clone + RelativeDeviation,
+	MinResponders: Get<u32>,
+	MaxDeviation: Get<Permill>,
+	F: FnMut(&T, &T) -> Ordering,
+{
+	let min_responders = MinResponders::get() as usize;
+	if items.len() < min_responders {
+		return None;
+	}
+
+	let median = weighted_median_by(items.clone(), &mut compare)?;
+	let max_deviation = MaxDeviation::get();
+	let items: Vec<_> = items
+		.into_iter()
+		.filter(|(item, _)| item.is_within(&median, max_deviation))
+		.collect();
+	if items.len() < min_responders {
+		return None;
+	}
+
+	Some(items)
+}
+
+/// Create a data provider returning the median of the values of
+/// `$provider`s, averaging the two middle values for an even count, see
+/// `arithmetic_median`. `$value` must support checked arithmetic for this.
+/// `$timestamped_value` must be a `TimestampedValue`, and the timestamp of
+/// the median is the oldest of the values, see
+/// `arithmetic_median_timestamped`.
 #[macro_export]
 macro_rules! create_median_value_data_provider {
 	($name:ident, $key:ty, $value:ty, $timestamped_value:ty, [$( $provider:ty ),*]) => {
@@ -48,7 +257,7 @@ macro_rules! create_median_value_data_provider {
 						values.push(v);
 					}
 				)*
-				$crate::data_provider::median(values)
+				$crate::data_provider::arithmetic_median(values)
 			}
 		}
 		impl $crate::DataProviderExtended<$key, $timestamped_value> for $name {
@@ -59,7 +268,61 @@ macro_rules! create_median_value_data_provider {
 						values.push(v);
 					}
 				)*
-				$crate::data_provider::median(values)
+				$crate::data_provider::arithmetic_median_timestamped(values)
+			}
+			fn get_all_values() -> Vec<($key, Option<$timestamped_value>)> {
+				let mut keys = sp_std::collections::btree_set::BTreeSet::new();
+				$(
+					<$provider as $crate::DataProviderExtended<$key, $timestamped_value>>::get_all_values()
+						.into_iter()
+						.for_each(|(k, _)| { keys.insert(k); });
+				)*
+				keys.into_iter().map(|k| (k, Self::get_no_op(&k))).collect()
+			}
+		}
+	}
+}
+
+/// Like `create_median_value_data_provider`, but each provider has a weight,
+/// at least `$min_responders` (a `Get<u32>`) providers must answer, and
+/// answers further than `$max_deviation` (a `Get<Permill>`) from the weighted
+/// median are dropped before taking the weighted median of the rest. See
+/// `quorum_weighted_median`.
+///
+/// Example:
+/// ```ignore
+/// create_weighted_median_value_data_provider!(
+///     Prices, CurrencyId, Price, TimestampedPrice, MinResponders, MaxDeviation,
+///     [ChainlinkOracle => 2, BandOracle => 1, SetheumOracle => 1]
+/// );
+/// ```
+#[macro_export]
+macro_rules! create_weighted_median_value_data_provider {
+	(
+		$name:ident, $key:ty, $value:ty, $timestamped_value:ty, $min_responders:ty, $max_deviation:ty,
+		[$( $provider:ty => $weight:expr ),*]
+	) => {
+		pub struct $name;
+		impl $crate::DataProvider<$key, $value> for $name {
+			fn get(key: &$key) -> Option<$value> {
+				let mut values = vec![];
+				$(
+					if let Some(v) = <$provider as $crate::DataProvider<$key, $value>>::get(&key) {
+						values.push((v, $weight));
+					}
+				)*
+				$crate::data_provider::quorum_weighted_median::<_, $min_responders, $max_deviation>(values)
+			}
+		}
+		impl $crate::DataProviderExtended<$key, $timestamped_value> for $name {
+			fn get_no_op(key: &$key) -> Option<$timestamped_value> {
+				let mut values = vec![];
+				$(
+					if let Some(v) = <$provider as $crate::DataProviderExtended<$key, $timestamped_value>>::get_no_op(&key) {
+						values.push((v, $weight));
+					}
+				)*
+				$crate::data_provider::quorum_weighted_median_timestamped::<_, _, $min_responders, $max_deviation>(values)
 			}
 			fn get_all_values() -> Vec<($key, Option<$timestamped_value>)> {
 				let mut keys = sp_std::collections::btree_set::BTreeSet::new();
@@ -77,6 +340,7 @@ macro_rules! create_median_value_data_provider {
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use frame_support::parameter_types;
 	use sp_std::cell::RefCell;
 
 	thread_local! {
@@ -87,7 +351,7 @@ mod tests {
 	}
 
 	macro_rules! mock_data_provider {
-		($provider:ident, $price:ident) => {
+		($provider:ident, $price:ident, $timestamp:expr) => {
 			pub struct $provider;
 			impl $provider {
 				fn set_price(price: Option<u8>) {
@@ -99,42 +363,175 @@ mod tests {
 					$price.with(|v| *v.borrow())
 				}
 			}
-			impl DataProviderExtended<u8, u8> for $provider {
-				fn get_no_op(_: &u8) -> Option<u8> {
-					$price.with(|v| *v.borrow())
+			impl DataProviderExtended<u8, TimestampedValue<u8, u8>> for $provider {
+				fn get_no_op(_: &u8) -> Option<TimestampedValue<u8, u8>> {
+					$price.with(|v| {
+						v.borrow().map(|value| TimestampedValue {
+							value,
+							timestamp: $timestamp,
+						})
+					})
 				}
-				fn get_all_values() -> Vec<(u8, Option<u8>)> {
+				fn get_all_values() -> Vec<(u8, Option<TimestampedValue<u8, u8>>)> {
 					vec![(0, Self::get_no_op(&0))]
 				}
 			}
 		};
 	}
 
-	mock_data_provider!(Provider1, MOCK_PRICE_1);
-	mock_data_provider!(Provider2, MOCK_PRICE_2);
-	mock_data_provider!(Provider3, MOCK_PRICE_3);
-	mock_data_provider!(Provider4, MOCK_PRICE_4);
+	mock_data_provider!(Provider1, MOCK_PRICE_1, 10);
+	mock_data_provider!(Provider2, MOCK_PRICE_2, 20);
+	mock_data_provider!(Provider3, MOCK_PRICE_3, 30);
+	mock_data_provider!(Provider4, MOCK_PRICE_4, 40);
 
-	create_median_value_data_provider!(Providers, u8, u8, u8, [Provider1, Provider2, Provider3, Provider4]);
+	create_median_value_data_provider!(
+		Providers,
+		u8,
+		u8,
+		TimestampedValue<u8, u8>,
+		[Provider1, Provider2, Provider3, Provider4]
+	);
+
+	fn timestamped(value: Option<u8>, timestamp: u8) -> Option<TimestampedValue<u8, u8>> {
+		value.map(|value| TimestampedValue { value, timestamp })
+	}
 
 	#[test]
 	fn median_value_data_provider_works() {
 		assert_eq!(<Providers as DataProvider<_, _>>::get(&0), None);
 
 		let data = vec![
-			(vec![None, None, None, Some(1)], Some(1)),
-			(vec![None, None, Some(2), Some(1)], Some(2)),
-			(vec![Some(5), Some(2), None, Some(7)], Some(5)),
-			(vec![Some(5), Some(13), Some(2), Some(7)], Some(7)),
+			(vec![None, None, None, Some(1)], Some(1), 40),
+			(vec![None, None, Some(2), Some(1)], Some(1), 30),
+			(vec![Some(5), Some(2), None, Some(7)], Some(5), 10),
+			(vec![Some(5), Some(13), Some(2), Some(7)], Some(6), 10),
+			// equal values are not told apart by timestamp
+			(vec![None, Some(3), Some(3), Some(3)], Some(3), 20),
 		];
 
-		for (values, target) in data {
+		for (values, target, timestamp) in data {
 			Provider1::set_price(values[0]);
 			Provider2::set_price(values[1]);
 			Provider3::set_price(values[2]);
 			Provider4::set_price(values[3]);
 
 			assert_eq!(<Providers as DataProvider<_, _>>::get(&0), target);
+			assert_eq!(
+				<Providers as DataProviderExtended<_, _>>::get_no_op(&0),
+				timestamped(target, timestamp)
+			);
+		}
+	}
+
+	parameter_types! {
+		pub const MinResponders: u32 = 2;
+		pub const MaxDeviation: Permill = Permill::from_percent(20);
+	}
+
+	create_weighted_median_value_data_provider!(
+		WeightedProviders,
+		u8,
+		u8,
+		TimestampedValue<u8, u8>,
+		MinResponders,
+		MaxDeviation,
+		[Provider1 => 3, Provider2 => 1, Provider3 => 1, Provider4 => 1]
+	);
+
+	#[test]
+	fn weighted_median_value_data_provider_works() {
+		let data = vec![
+			// not enough responders
+			(vec![None, None, None, Some(1)], None, 0),
+			(vec![None, None, Some(100), Some(90)], Some(100), 30),
+			// provider 1 outweighs the others
+			(vec![Some(100), Some(90), Some(110), None], Some(100), 10),
+			// a single outlier is dropped
+			(vec![None, Some(100), Some(105), Some(200)], Some(105), 20),
+			(vec![Some(100), Some(1), Some(250), None], None, 0),
+		];
+
+		for (values, target, timestamp) in data {
+			Provider1::set_price(values[0]);
+			Provider2::set_price(values[1]);
+			Provider3::set_price(values[2]);
+			Provider4::set_price(values[3]);
+
+			assert_eq!(<WeightedProviders as DataProvider<_, _>>::get(&0), target);
+			assert_eq!(
+				<WeightedProviders as DataProviderExtended<_, _>>::get_no_op(&0),
+				timestamped(target, timestamp)
+			);
+		}
+	}
+
+	#[test]
+	fn median_works() {
+		assert_eq!(lower_median::<u8>(vec![]), None);
+		assert_eq!(upper_median::<u8>(vec![]), None);
+		assert_eq!(arithmetic_median::<u8>(vec![]), None);
+
+		assert_eq!(lower_median(vec![5u8, 13, 2, 7]), Some(5));
+		assert_eq!(upper_median(vec![5u8, 13, 2, 7]), Some(7));
+		assert_eq!(median(vec![5u8, 13, 2, 7]), Some(7));
+		assert_eq!(arithmetic_median(vec![5u8, 13, 2, 7]), Some(6));
+		assert_eq!(arithmetic_median(vec![5u8, 13, 2, 8]), Some(6));
+		assert_eq!(arithmetic_median(vec![250u8, 254]), Some(252));
+
+		for items in vec![vec![3u8], vec![5, 13, 2], vec![9, 1, 5, 5, 3]] {
+			let expected = {
+				let mut sorted = items.clone();
+				sorted.sort();
+				sorted[sorted.len() / 2]
+			};
+			assert_eq!(lower_median(items.clone()), Some(expected));
+			assert_eq!(upper_median(items.clone()), Some(expected));
+			assert_eq!(arithmetic_median(items), Some(expected));
 		}
+
+		assert_eq!(
+			arithmetic_median(vec![
+				FixedU128::saturating_from_integer(1),
+				FixedU128::saturating_from_integer(2)
+			]),
+			Some(FixedU128::saturating_from_rational(3, 2))
+		);
+	}
+
+	#[test]
+	fn median_timestamped_works() {
+		let tv = |value: u8, timestamp: u8| TimestampedValue { value, timestamp };
+		assert_eq!(median_timestamped::<u8, u8>(vec![]), None);
+		assert_eq!(
+			median_timestamped(vec![tv(5, 3), tv(2, 9), tv(7, 1), tv(5, 2)]),
+			Some(tv(5, 1))
+		);
+		assert_eq!(median_timestamped(vec![tv(4, 3), tv(4, 9), tv(4, 5)]), Some(tv(4, 3)));
+	}
+
+	#[test]
+	fn weighted_median_works() {
+		assert_eq!(weighted_median::<u8>(vec![]), None);
+		assert_eq!(weighted_median(vec![(1u8, 0), (2, 0)]), None);
+		assert_eq!(weighted_median(vec![(3u8, 1), (1, 1), (2, 1), (9, 1)]), Some(3));
+		assert_eq!(weighted_median(vec![(3u8, 1), (1, 1), (2, 1), (9, 4)]), Some(9));
+		assert_eq!(weighted_median(vec![(3u8, 1), (1, 3), (2, 1), (9, 1)]), Some(2));
+	}
+
+	#[test]
+	fn relative_deviation_works() {
+		let max = Permill::from_percent(10);
+		assert!(110u32.is_within(&100, max));
+		assert!(90u32.is_within(&100, max));
+		assert!(!111u32.is_within(&100, max));
+		assert!(!0u8.is_within(&1, max));
+		assert!(FixedU128::saturating_from_rational(21, 20).is_within(&FixedU128::saturating_from_integer(1), max));
+		assert!(!TimestampedValue { value: 80u8, timestamp: 1u64 }.is_within(
+			&TimestampedValue {
+				value: 100,
+				timestamp: 2
+			},
+			max
+		));
 	}
 }
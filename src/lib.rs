@@ -10,33 +10,61 @@ use sp_std::{
 #[cfg(feature = "std")]
 use serde::{Deserialize, Serialize};
 
+pub use adapters::{Stp258AssetAdapter, Stp258CurrencyRouter, Stp258NativeAdapter};
 pub use auction::{Auction, AuctionHandler, AuctionInfo, OnNewBidResult};
 pub use stp258::{
 	BalanceStatus, 
-	FetchPrice,  LockIdentifier,  OnDust, 
-	SerpMarket, SerpTes, SerpTesPriceProvider, 
+	FetchCurrencyPrice, FetchPrice,  LockIdentifier,  OnDust, 
+	SerpAdjustment, SerpDirection, SerpMarket, SerpTes, SerpTesPriceProvider, 
 	Stp258Asset, Stp258AssetExtended, Stp258AssetLockable, 
 	Stp258AssetReservable, Stp258Currency, Stp258CurrencyExtended, 
-	Stp258CurrencyLockable, Stp258CurrencyReservable,
+	Stp258CurrencyImbalanced, Stp258CurrencyLockable, Stp258CurrencyReservable,
 };
+pub use combine_data::{
+	CombineWeightedData, CombinedFeeder, CombinedFeederStore, MedianCombineData, TrimmedMeanCombineData,
+	WeightedMedianCombineData,
+};
+pub use data_feeder::{FeedError, FeedPolicy, GuardedFeeder, OracleMembership};
 pub use data_provider::{DataFeeder, DataProvider, DataProviderExtended};
 pub use get_by_key::GetByKey;
+pub use metadata::Stp258CurrencyMetadata;
 pub use nft::NFT;
-pub use price::{DefaultPriceProvider, PriceProvider};
+pub use peg::{PegFromKey, PegProvider};
+pub use price::{
+	DataProviderFetchPrice, DefaultPriceProvider, FreshPriceProvider, PriceError, PriceProvider, RoutedPriceProvider,
+	TryPriceProvider,
+};
 pub use rewards::RewardHandler;
-// pub use serp_market::SerpMarket; //{SerpMarket, SerpingStatus};
-// pub use serp_tes::{SerpTes, ElastAdjustmentFrequency};
+pub use serp_auction::{SerpDownAuction, SerpDownAuctionItem, SerpDownAuctionStore};
+pub use serp_policy::{FixedBand, Pid, PidState, Proportional, SerpElasticityPolicy};
+pub use serp_schedule::{ElastAdjustmentFrequency, ElastAdjustmentSchedule, SerpSchedule};
+pub use serp_supply::{calculate_supply_change, Rounding, SupplyChange, SupplyChangeError};
+pub use setheum_currency::{Currency, LockableCurrency, ReservableCurrency};
+pub use twap::{DefaultTwapPriceProvider, TwapObservation, TwapObservations, TwapPriceProvider, TwapStore};
 pub mod account;
+pub mod adapters;
 pub mod arithmetic;
 pub mod auction;
+pub mod combine_data;
+#[cfg(any(feature = "mock", test))]
+pub mod conformance;
 pub mod stp258;
+pub mod data_feeder;
 pub mod data_provider;
 pub mod get_by_key;
+pub mod metadata;
+#[cfg(any(feature = "mock", test))]
+pub mod mock;
 pub mod nft;
+pub mod peg;
 pub mod price;
 pub mod rewards;
-// pub mod serp_market;
-// pub mod serp_tes;
+pub mod serp_auction;
+pub mod serp_policy;
+pub mod serp_schedule;
+pub mod serp_supply;
+pub mod setheum_currency;
+pub mod twap;
 
 /// New data handler
 #[impl_trait_for_tuples::impl_for_tuples(30)]
@@ -45,6 +73,15 @@ pub trait OnNewData<AccountId, Key, Value> {
 	fn on_new_data(who: &AccountId, key: &Key, value: &Value);
 }
 
+/// SERP supply adjustment handler
+#[impl_trait_for_tuples::impl_for_tuples(30)]
+pub trait OnSerpAdjustment<CurrencyId, Balance> {
+	/// Stable currency supply was expanded
+	fn on_expand(adjustment: &SerpAdjustment<CurrencyId, Balance>);
+	/// Stable currency supply was contracted
+	fn on_contract(adjustment: &SerpAdjustment<CurrencyId, Balance>);
+}
+
 /// Combine data provided by operators
 pub trait CombineData<Key, TimestampedValue> {
 	/// Combine data provided by operators
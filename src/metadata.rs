@@ -0,0 +1,116 @@
+use crate::arithmetic::SimpleArithmetic;
+use sp_std::prelude::Vec;
+
+/// Display metadata of a currency.
+///
+/// Amounts are always held in base units; `decimals` is the number of base
+/// unit digits in one whole unit, so `unit_of` is `10^decimals`.
+pub trait Stp258CurrencyMetadata<CurrencyId, Balance: SimpleArithmetic + Copy> {
+	/// Number of decimals of the currency `currency_id`.
+	fn decimals(currency_id: CurrencyId) -> u8;
+
+	/// Ticker symbol of the currency `currency_id`, e.g. `b"SETT"`.
+	fn symbol(currency_id: CurrencyId) -> Vec<u8>;
+
+	/// Human readable name of the currency `currency_id`.
+	fn name(currency_id: CurrencyId) -> Vec<u8>;
+
+	/// Amount of base units in one whole unit of the currency `currency_id`,
+	/// or `None` if `10^decimals` does not fit in `Balance`.
+	///
+	/// Not named `base_unit`, as `Stp258Currency::base_unit` takes the same
+	/// argument and a currency implementing both traits would need fully
+	/// qualified calls for either.
+	fn unit_of(currency_id: CurrencyId) -> Option<Balance> {
+		base_unit(Self::decimals(currency_id))
+	}
+}
+
+/// `10^decimals`, or `None` on overflow.
+pub fn base_unit<Balance: SimpleArithmetic + Copy>(decimals: u8) -> Option<Balance> {
+	let ten = Balance::from(10u8);
+	let mut unit = Balance::one();
+	for _ in 0..decimals {
+		unit = unit.checked_mul(&ten)?;
+	}
+	Some(unit)
+}
+
+/// Convert an amount of whole units into base units, or `None` on overflow.
+pub fn whole_to_base<Balance: SimpleArithmetic + Copy>(whole: Balance, decimals: u8) -> Option<Balance> {
+	whole.checked_mul(&base_unit(decimals)?)
+}
+
+/// Split an amount of base units into `(whole, remainder)`, the remainder
+/// being in base units. `None` if `10^decimals` overflows `Balance`.
+pub fn base_to_whole<Balance: SimpleArithmetic + Copy>(amount: Balance, decimals: u8) -> Option<(Balance, Balance)> {
+	let unit = base_unit(decimals)?;
+	Some((amount / unit, amount % unit))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const SETT: u32 = 0;
+	const DNAR: u32 = 1;
+
+	pub struct MockMetadata;
+	impl Stp258CurrencyMetadata<u32, u64> for MockMetadata {
+		fn decimals(currency_id: u32) -> u8 {
+			match currency_id {
+				SETT => 4,
+				DNAR => 12,
+				_ => 30,
+			}
+		}
+
+		fn symbol(currency_id: u32) -> Vec<u8> {
+			match currency_id {
+				SETT => b"SETT".to_vec(),
+				_ => b"DNAR".to_vec(),
+			}
+		}
+
+		fn name(currency_id: u32) -> Vec<u8> {
+			match currency_id {
+				SETT => b"Setter".to_vec(),
+				_ => b"Dinar".to_vec(),
+			}
+		}
+	}
+
+	#[test]
+	fn base_unit_should_work() {
+		assert_eq!(base_unit::<u64>(0), Some(1));
+		assert_eq!(base_unit::<u64>(4), Some(10_000));
+		assert_eq!(base_unit::<u64>(19), Some(10_000_000_000_000_000_000));
+		assert_eq!(base_unit::<u64>(20), None);
+		assert_eq!(base_unit::<u128>(38), Some(10u128.pow(38)));
+		assert_eq!(base_unit::<u128>(39), None);
+	}
+
+	#[test]
+	fn whole_to_base_should_work() {
+		assert_eq!(whole_to_base::<u64>(3, 4), Some(30_000));
+		assert_eq!(whole_to_base::<u64>(0, 20), None);
+		assert_eq!(whole_to_base::<u64>(u64::max_value(), 1), None);
+	}
+
+	#[test]
+	fn base_to_whole_should_work() {
+		assert_eq!(base_to_whole::<u64>(30_001, 4), Some((3, 1)));
+		assert_eq!(base_to_whole::<u64>(9_999, 4), Some((0, 9_999)));
+		assert_eq!(base_to_whole::<u64>(42, 0), Some((42, 0)));
+		assert_eq!(base_to_whole::<u64>(42, 20), None);
+	}
+
+	#[test]
+	fn metadata_unit_of_should_follow_decimals() {
+		assert_eq!(MockMetadata::unit_of(SETT), Some(10_000));
+		assert_eq!(MockMetadata::unit_of(DNAR), Some(1_000_000_000_000));
+		assert_eq!(MockMetadata::unit_of(2), None);
+		assert_eq!(MockMetadata::symbol(SETT), b"SETT".to_vec());
+		assert_eq!(MockMetadata::name(DNAR), b"Dinar".to_vec());
+	}
+}
@@ -0,0 +1,1158 @@
+//! In-memory reference implementation of the `Stp258Currency` traits.
+//!
+//! `Ledger` holds the balances, locks and issuance of a multi-currency system
+//! and implements the documented semantics of `Stp258Currency`,
+//! `Stp258CurrencyLockable` and `Stp258CurrencyReservable`.
+//! `create_mock_stp258_currency!` wraps a thread local `Ledger` into a type
+//! implementing all five `Stp258Currency*` traits, for use in tests, with
+//! `PositiveImbalance` and `NegativeImbalance` as its imbalances.
+
+use crate::{
+	get_by_key::GetByKey,
+	stp258::{BalanceStatus, LockIdentifier},
+};
+use frame_support::{
+	ensure,
+	traits::{Imbalance, TryDrop},
+};
+use sp_runtime::{
+	traits::{AtLeast32BitUnsigned, Zero},
+	DispatchError, DispatchResult, RuntimeDebug,
+};
+use sp_std::{collections::btree_map::BTreeMap, marker::PhantomData, prelude::*, result};
+
+/// Errors returned by the mock ledger.
+#[derive(Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum Error {
+	/// The free balance is too low.
+	BalanceTooLow,
+	/// The free balance is locked.
+	LiquidityRestrictions,
+	/// The account would be created with less than the existential deposit.
+	ExistentialDeposit,
+	/// The balance of the account would overflow.
+	BalanceOverflow,
+	/// The total issuance would overflow.
+	TotalIssuanceOverflow,
+	/// The beneficiary account does not exist.
+	DeadAccount,
+	/// The signed amount cannot be converted into a balance.
+	AmountIntoBalanceFailed,
+}
+
+impl From<Error> for DispatchError {
+	fn from(e: Error) -> Self {
+		DispatchError::Other(match e {
+			Error::BalanceTooLow => "BalanceTooLow",
+			Error::LiquidityRestrictions => "LiquidityRestrictions",
+			Error::ExistentialDeposit => "ExistentialDeposit",
+			Error::BalanceOverflow => "BalanceOverflow",
+			Error::TotalIssuanceOverflow => "TotalIssuanceOverflow",
+			Error::DeadAccount => "DeadAccount",
+			Error::AmountIntoBalanceFailed => "AmountIntoBalanceFailed",
+		})
+	}
+}
+
+/// Balances of an account under one currency.
+#[derive(Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub struct AccountData<Balance> {
+	/// Balance that can be transferred, withdrawn or reserved.
+	pub free: Balance,
+	/// Balance set aside by `reserve`.
+	pub reserved: Balance,
+}
+
+impl<Balance: Zero> Default for AccountData<Balance> {
+	fn default() -> Self {
+		Self {
+			free: Zero::zero(),
+			reserved: Zero::zero(),
+		}
+	}
+}
+
+impl<Balance: AtLeast32BitUnsigned + Copy> AccountData<Balance> {
+	/// The combined free and reserved balance.
+	pub fn total(&self) -> Balance {
+		self.free.saturating_add(self.reserved)
+	}
+}
+
+/// An in-memory multi-currency ledger.
+///
+/// Accounts whose total balance falls below the existential deposit of
+/// `ExistentialDeposits` are reaped: the remaining dust is burned from the
+/// total issuance and queued, to be collected by `take_dust`.
+pub struct Ledger<AccountId, CurrencyId, Balance, ExistentialDeposits> {
+	accounts: BTreeMap<(CurrencyId, AccountId), AccountData<Balance>>,
+	locks: BTreeMap<(CurrencyId, AccountId), BTreeMap<LockIdentifier, Balance>>,
+	issuance: BTreeMap<CurrencyId, Balance>,
+	dust: Vec<(AccountId, CurrencyId, Balance)>,
+	_marker: PhantomData<ExistentialDeposits>,
+}
+
+impl<AccountId, CurrencyId, Balance, ExistentialDeposits> Default
+	for Ledger<AccountId, CurrencyId, Balance, ExistentialDeposits>
+where
+	AccountId: Ord,
+	CurrencyId: Ord,
+{
+	fn default() -> Self {
+		Self {
+			accounts: BTreeMap::new(),
+			locks: BTreeMap::new(),
+			issuance: BTreeMap::new(),
+			dust: Vec::new(),
+			_marker: PhantomData,
+		}
+	}
+}
+
+impl<AccountId, CurrencyId, Balance, ExistentialDeposits> Ledger<AccountId, CurrencyId, Balance, ExistentialDeposits>
+where
+	AccountId: Ord + Clone,
+	CurrencyId: Ord + Copy,
+	Balance: AtLeast32BitUnsigned + Copy,
+	ExistentialDeposits: GetByKey<CurrencyId, Balance>,
+{
+	/// Existential deposit of `currency_id`.
+	pub fn minimum_balance(currency_id: CurrencyId) -> Balance {
+		ExistentialDeposits::get(&currency_id)
+	}
+
+	/// The total amount of issuance of `currency_id`.
+	pub fn total_issuance(&self, currency_id: CurrencyId) -> Balance {
+		self.issuance.get(&currency_id).copied().unwrap_or_else(Zero::zero)
+	}
+
+	/// The balances of `who` under `currency_id`.
+	pub fn account(&self, currency_id: CurrencyId, who: &AccountId) -> AccountData<Balance> {
+		self.accounts
+			.get(&(currency_id, who.clone()))
+			.copied()
+			.unwrap_or_default()
+	}
+
+	/// The combined balance of `who` under `currency_id`.
+	pub fn total_balance(&self, currency_id: CurrencyId, who: &AccountId) -> Balance {
+		self.account(currency_id, who).total()
+	}
+
+	/// The free balance of `who` under `currency_id`.
+	pub fn free_balance(&self, currency_id: CurrencyId, who: &AccountId) -> Balance {
+		self.account(currency_id, who).free
+	}
+
+	/// The reserved balance of `who` under `currency_id`.
+	pub fn reserved_balance(&self, currency_id: CurrencyId, who: &AccountId) -> Balance {
+		self.account(currency_id, who).reserved
+	}
+
+	/// The part of the free balance of `who` that is locked, which is the
+	/// largest of its locks.
+	pub fn frozen_balance(&self, currency_id: CurrencyId, who: &AccountId) -> Balance {
+		self.locks
+			.get(&(currency_id, who.clone()))
+			.and_then(|locks| locks.values().max().copied())
+			.unwrap_or_else(Zero::zero)
+	}
+
+	/// Take the dust of the accounts reaped since the last call.
+	pub fn take_dust(&mut self) -> Vec<(AccountId, CurrencyId, Balance)> {
+		sp_std::mem::take(&mut self.dust)
+	}
+
+	pub fn ensure_can_withdraw(&self, currency_id: CurrencyId, who: &AccountId, amount: Balance) -> DispatchResult {
+		if amount.is_zero() {
+			return Ok(());
+		}
+
+		let new_balance = self
+			.free_balance(currency_id, who)
+			.checked_sub(&amount)
+			.ok_or(Error::BalanceTooLow)?;
+		ensure!(
+			new_balance >= self.frozen_balance(currency_id, who),
+			Error::LiquidityRestrictions
+		);
+		Ok(())
+	}
+
+	pub fn transfer(
+		&mut self,
+		currency_id: CurrencyId,
+		from: &AccountId,
+		to: &AccountId,
+		amount: Balance,
+	) -> DispatchResult {
+		if amount.is_zero() || from == to {
+			return Ok(());
+		}
+
+		self.ensure_can_withdraw(currency_id, from, amount)?;
+		self.ensure_can_receive(currency_id, to, amount)?;
+
+		self.mutate_account(currency_id, from, |account| account.free -= amount);
+		self.mutate_account(currency_id, to, |account| account.free += amount);
+		Ok(())
+	}
+
+	pub fn deposit(&mut self, currency_id: CurrencyId, who: &AccountId, amount: Balance) -> DispatchResult {
+		if amount.is_zero() {
+			return Ok(());
+		}
+
+		let new_issuance = self
+			.total_issuance(currency_id)
+			.checked_add(&amount)
+			.ok_or(Error::TotalIssuanceOverflow)?;
+		self.ensure_can_receive(currency_id, who, amount)?;
+
+		self.issuance.insert(currency_id, new_issuance);
+		self.mutate_account(currency_id, who, |account| account.free += amount);
+		Ok(())
+	}
+
+	pub fn withdraw(&mut self, currency_id: CurrencyId, who: &AccountId, amount: Balance) -> DispatchResult {
+		if amount.is_zero() {
+			return Ok(());
+		}
+
+		self.ensure_can_withdraw(currency_id, who, amount)?;
+
+		self.burn(currency_id, amount);
+		self.mutate_account(currency_id, who, |account| account.free -= amount);
+		Ok(())
+	}
+
+	pub fn can_slash(&self, currency_id: CurrencyId, who: &AccountId, value: Balance) -> bool {
+		value.is_zero() || self.free_balance(currency_id, who) >= value
+	}
+
+	/// Slash the free balance first, then the reserved balance. Locks are
+	/// ignored.
+	pub fn slash(&mut self, currency_id: CurrencyId, who: &AccountId, amount: Balance) -> Balance {
+		if amount.is_zero() {
+			return amount;
+		}
+
+		let account = self.account(currency_id, who);
+		let free_slashed_amount = account.free.min(amount);
+		let reserved_slashed_amount = account.reserved.min(amount - free_slashed_amount);
+		let slashed_amount = free_slashed_amount + reserved_slashed_amount;
+
+		self.burn(currency_id, slashed_amount);
+		self.mutate_account(currency_id, who, |account| {
+			account.free -= free_slashed_amount;
+			account.reserved -= reserved_slashed_amount;
+		});
+		amount - slashed_amount
+	}
+
+	/// Set the lock `lock_id`, replacing any existing one. A zero `amount` is
+	/// a no-op.
+	pub fn set_lock(&mut self, lock_id: LockIdentifier, currency_id: CurrencyId, who: &AccountId, amount: Balance) {
+		if amount.is_zero() {
+			return;
+		}
+
+		self.locks
+			.entry((currency_id, who.clone()))
+			.or_insert_with(BTreeMap::new)
+			.insert(lock_id, amount);
+	}
+
+	/// Raise the lock `lock_id` to at least `amount`, creating it if needed. A
+	/// zero `amount` is a no-op.
+	pub fn extend_lock(&mut self, lock_id: LockIdentifier, currency_id: CurrencyId, who: &AccountId, amount: Balance) {
+		if amount.is_zero() {
+			return;
+		}
+
+		self.locks
+			.entry((currency_id, who.clone()))
+			.or_insert_with(BTreeMap::new)
+			.entry(lock_id)
+			.and_modify(|locked| *locked = (*locked).max(amount))
+			.or_insert(amount);
+	}
+
+	pub fn remove_lock(&mut self, lock_id: LockIdentifier, currency_id: CurrencyId, who: &AccountId) {
+		let key = (currency_id, who.clone());
+		if let Some(locks) = self.locks.get_mut(&key) {
+			locks.remove(&lock_id);
+			if locks.is_empty() {
+				self.locks.remove(&key);
+			}
+		}
+	}
+
+	pub fn can_reserve(&self, currency_id: CurrencyId, who: &AccountId, value: Balance) -> bool {
+		self.ensure_can_withdraw(currency_id, who, value).is_ok()
+	}
+
+	pub fn slash_reserved(&mut self, currency_id: CurrencyId, who: &AccountId, value: Balance) -> Balance {
+		if value.is_zero() {
+			return value;
+		}
+
+		let actual = self.reserved_balance(currency_id, who).min(value);
+		self.burn(currency_id, actual);
+		self.mutate_account(currency_id, who, |account| account.reserved -= actual);
+		value - actual
+	}
+
+	pub fn reserve(&mut self, currency_id: CurrencyId, who: &AccountId, value: Balance) -> DispatchResult {
+		if value.is_zero() {
+			return Ok(());
+		}
+
+		self.ensure_can_withdraw(currency_id, who, value)?;
+
+		self.mutate_account(currency_id, who, |account| {
+			account.free -= value;
+			account.reserved += value;
+		});
+		Ok(())
+	}
+
+	pub fn unreserve(&mut self, currency_id: CurrencyId, who: &AccountId, value: Balance) -> Balance {
+		if value.is_zero() {
+			return value;
+		}
+
+		let actual = self.reserved_balance(currency_id, who).min(value);
+		self.mutate_account(currency_id, who, |account| {
+			account.reserved -= actual;
+			account.free += actual;
+		});
+		value - actual
+	}
+
+	pub fn repatriate_reserved(
+		&mut self,
+		currency_id: CurrencyId,
+		slashed: &AccountId,
+		beneficiary: &AccountId,
+		value: Balance,
+		status: BalanceStatus,
+	) -> result::Result<Balance, DispatchError> {
+		if value.is_zero() {
+			return Ok(value);
+		}
+
+		if slashed == beneficiary {
+			return match status {
+				BalanceStatus::Free => Ok(self.unreserve(currency_id, slashed, value)),
+				BalanceStatus::Reserved => Ok(value.saturating_sub(self.reserved_balance(currency_id, slashed))),
+			};
+		}
+
+		ensure!(
+			!self.total_balance(currency_id, beneficiary).is_zero(),
+			Error::DeadAccount
+		);
+
+		let actual = self.reserved_balance(currency_id, slashed).min(value);
+		self.mutate_account(currency_id, slashed, |account| account.reserved -= actual);
+		self.mutate_account(currency_id, beneficiary, |account| match status {
+			BalanceStatus::Free => account.free += actual,
+			BalanceStatus::Reserved => account.reserved += actual,
+		});
+		Ok(value - actual)
+	}
+
+	/// Ensure `who` can be credited with `amount` without overflowing or
+	/// being created below the existential deposit.
+	fn ensure_can_receive(&self, currency_id: CurrencyId, who: &AccountId, amount: Balance) -> DispatchResult {
+		let new_total = self
+			.total_balance(currency_id, who)
+			.checked_add(&amount)
+			.ok_or(Error::BalanceOverflow)?;
+		ensure!(
+			new_total >= Self::minimum_balance(currency_id),
+			Error::ExistentialDeposit
+		);
+		Ok(())
+	}
+
+	/// Reduce the total issuance of `currency_id` by up to `amount`, without
+	/// touching any balance. Returns the amount burned.
+	pub fn burn(&mut self, currency_id: CurrencyId, amount: Balance) -> Balance {
+		let issuance = self.total_issuance(currency_id);
+		let amount = amount.min(issuance);
+		self.issuance.insert(currency_id, issuance - amount);
+		amount
+	}
+
+	/// Increase the total issuance of `currency_id` by up to `amount`, without
+	/// touching any balance. Returns the amount issued.
+	pub fn issue(&mut self, currency_id: CurrencyId, amount: Balance) -> Balance {
+		let issuance = self.total_issuance(currency_id);
+		let amount = amount.min(Balance::max_value() - issuance);
+		self.issuance.insert(currency_id, issuance + amount);
+		amount
+	}
+
+	/// Credit `value` to the free balance of an existing `who`, without
+	/// changing the total issuance.
+	pub fn deposit_into_existing(&mut self, currency_id: CurrencyId, who: &AccountId, value: Balance) -> DispatchResult {
+		if value.is_zero() {
+			return Ok(());
+		}
+
+		ensure!(
+			!self.total_balance(currency_id, who).is_zero(),
+			Error::DeadAccount
+		);
+		self.ensure_can_receive(currency_id, who, value)?;
+
+		self.mutate_account(currency_id, who, |account| account.free += value);
+		Ok(())
+	}
+
+	/// Credit `value` to the free balance of `who`, creating it if needed,
+	/// without changing the total issuance. Returns the amount credited, which
+	/// is zero if `who` cannot receive `value`.
+	pub fn deposit_creating(&mut self, currency_id: CurrencyId, who: &AccountId, value: Balance) -> Balance {
+		if self.ensure_can_receive(currency_id, who, value).is_err() {
+			return Zero::zero();
+		}
+
+		self.mutate_account(currency_id, who, |account| account.free += value);
+		value
+	}
+
+	/// Debit `value` from the free balance of `who`, respecting locks, without
+	/// changing the total issuance.
+	pub fn withdraw_imbalance(&mut self, currency_id: CurrencyId, who: &AccountId, value: Balance) -> DispatchResult {
+		if value.is_zero() {
+			return Ok(());
+		}
+
+		self.ensure_can_withdraw(currency_id, who, value)?;
+
+		self.mutate_account(currency_id, who, |account| account.free -= value);
+		Ok(())
+	}
+
+	/// Mutate the balances of `who`, reaping the account if its total balance
+	/// falls below the existential deposit.
+	fn mutate_account(&mut self, currency_id: CurrencyId, who: &AccountId, f: impl FnOnce(&mut AccountData<Balance>)) {
+		let key = (currency_id, who.clone());
+		let mut account = self.accounts.remove(&key).unwrap_or_default();
+		f(&mut account);
+
+		let total = account.total();
+		if total.is_zero() {
+			return;
+		}
+
+		if total < Self::minimum_balance(currency_id) {
+			self.burn(currency_id, total);
+			self.dust.push((who.clone(), currency_id, total));
+		} else {
+			self.accounts.insert(key, account);
+		}
+	}
+}
+
+/// Adjusts the total issuance when a mock imbalance is dropped.
+pub trait AdjustIssuance<CurrencyId, Balance> {
+	/// Increase the total issuance of `currency_id` by `amount`.
+	fn increase_issuance(currency_id: CurrencyId, amount: Balance);
+	/// Decrease the total issuance of `currency_id` by `amount`.
+	fn decrease_issuance(currency_id: CurrencyId, amount: Balance);
+}
+
+/// Funds added to an account without increasing the total issuance. Dropping
+/// it increases the total issuance of its currency through `Issuance`.
+///
+/// A zero imbalance has no currency, and takes the currency of the first
+/// imbalance merged into or offset against it.
+#[must_use]
+pub struct PositiveImbalance<CurrencyId, Balance, Issuance>
+where
+	CurrencyId: Copy + PartialEq,
+	Balance: AtLeast32BitUnsigned + Copy,
+	Issuance: AdjustIssuance<CurrencyId, Balance>,
+{
+	currency_id: Option<CurrencyId>,
+	amount: Balance,
+	_marker: PhantomData<Issuance>,
+}
+
+/// Funds removed from an account without decreasing the total issuance.
+/// Dropping it decreases the total issuance of its currency through
+/// `Issuance`.
+///
+/// A zero imbalance has no currency, and takes the currency of the first
+/// imbalance merged into or offset against it.
+#[must_use]
+pub struct NegativeImbalance<CurrencyId, Balance, Issuance>
+where
+	CurrencyId: Copy + PartialEq,
+	Balance: AtLeast32BitUnsigned + Copy,
+	Issuance: AdjustIssuance<CurrencyId, Balance>,
+{
+	currency_id: Option<CurrencyId>,
+	amount: Balance,
+	_marker: PhantomData<Issuance>,
+}
+
+/// The currency of two imbalances combined into one.
+fn combined_currency<CurrencyId: Copy + PartialEq>(a: Option<CurrencyId>, b: Option<CurrencyId>) -> Option<CurrencyId> {
+	debug_assert!(
+		a.is_none() || b.is_none() || a == b,
+		"imbalances of different currencies combined"
+	);
+	a.or(b)
+}
+
+macro_rules! impl_imbalance {
+	($imbalance:ident, $opposite:ident, $on_drop:ident) => {
+		impl<CurrencyId, Balance, Issuance> $imbalance<CurrencyId, Balance, Issuance>
+		where
+			CurrencyId: Copy + PartialEq,
+			Balance: AtLeast32BitUnsigned + Copy,
+			Issuance: AdjustIssuance<CurrencyId, Balance>,
+		{
+			/// An imbalance of `amount` of `currency_id`.
+			pub fn new(currency_id: CurrencyId, amount: Balance) -> Self {
+				Self::with_currency(Some(currency_id), amount)
+			}
+
+			/// The currency of the imbalance, `None` if it is zero and was never
+			/// combined with another imbalance.
+			pub fn currency_id(&self) -> Option<CurrencyId> {
+				self.currency_id
+			}
+
+			fn with_currency(currency_id: Option<CurrencyId>, amount: Balance) -> Self {
+				Self {
+					currency_id,
+					amount,
+					_marker: PhantomData,
+				}
+			}
+
+			/// Consume the imbalance without adjusting the total issuance.
+			fn forget(self) -> (Option<CurrencyId>, Balance) {
+				let parts = (self.currency_id, self.amount);
+				sp_std::mem::forget(self);
+				parts
+			}
+		}
+
+		impl<CurrencyId, Balance, Issuance> TryDrop for $imbalance<CurrencyId, Balance, Issuance>
+		where
+			CurrencyId: Copy + PartialEq,
+			Balance: AtLeast32BitUnsigned + Copy,
+			Issuance: AdjustIssuance<CurrencyId, Balance>,
+		{
+			fn try_drop(self) -> result::Result<(), Self> {
+				self.drop_zero()
+			}
+		}
+
+		impl<CurrencyId, Balance, Issuance> Imbalance<Balance> for $imbalance<CurrencyId, Balance, Issuance>
+		where
+			CurrencyId: Copy + PartialEq,
+			Balance: AtLeast32BitUnsigned + Copy,
+			Issuance: AdjustIssuance<CurrencyId, Balance>,
+		{
+			type Opposite = $opposite<CurrencyId, Balance, Issuance>;
+
+			fn zero() -> Self {
+				Self::with_currency(None, Zero::zero())
+			}
+
+			fn drop_zero(self) -> result::Result<(), Self> {
+				if self.amount.is_zero() {
+					self.forget();
+					Ok(())
+				} else {
+					Err(self)
+				}
+			}
+
+			fn split(self, amount: Balance) -> (Self, Self) {
+				let (currency_id, total) = self.forget();
+				let first = total.min(amount);
+				(
+					Self::with_currency(currency_id, first),
+					Self::with_currency(currency_id, total - first),
+				)
+			}
+
+			fn merge(self, other: Self) -> Self {
+				let (currency_id, amount) = self.forget();
+				let (other_currency_id, other_amount) = other.forget();
+				Self::with_currency(
+					combined_currency(currency_id, other_currency_id),
+					amount.saturating_add(other_amount),
+				)
+			}
+
+			fn subsume(&mut self, other: Self) {
+				let (other_currency_id, other_amount) = other.forget();
+				self.currency_id = combined_currency(self.currency_id, other_currency_id);
+				self.amount = self.amount.saturating_add(other_amount);
+			}
+
+			fn offset(self, other: Self::Opposite) -> result::Result<Self, Self::Opposite> {
+				let (currency_id, amount) = self.forget();
+				let (other_currency_id, other_amount) = other.forget();
+				let currency_id = combined_currency(currency_id, other_currency_id);
+				if amount >= other_amount {
+					Ok(Self::with_currency(currency_id, amount - other_amount))
+				} else {
+					Err($opposite::with_currency(currency_id, other_amount - amount))
+				}
+			}
+
+			fn peek(&self) -> Balance {
+				self.amount
+			}
+		}
+
+		impl<CurrencyId, Balance, Issuance> Drop for $imbalance<CurrencyId, Balance, Issuance>
+		where
+			CurrencyId: Copy + PartialEq,
+			Balance: AtLeast32BitUnsigned + Copy,
+			Issuance: AdjustIssuance<CurrencyId, Balance>,
+		{
+			fn drop(&mut self) {
+				if let Some(currency_id) = self.currency_id {
+					if !self.amount.is_zero() {
+						Issuance::$on_drop(currency_id, self.amount);
+					}
+				}
+			}
+		}
+	};
+}
+
+impl_imbalance!(PositiveImbalance, NegativeImbalance, increase_issuance);
+impl_imbalance!(NegativeImbalance, PositiveImbalance, decrease_issuance);
+
+/// Create a type implementing `Stp258Currency`, `Stp258CurrencyExtended`,
+/// `Stp258CurrencyLockable`, `Stp258CurrencyReservable` and
+/// `Stp258CurrencyImbalanced` on top of a thread local `Ledger`.
+///
+/// `$existential_deposits` and `$base_units` implement
+/// `GetByKey<$currency_id, $balance>`, and `$on_dust` implements
+/// `OnDust<$account_id, $currency_id, $balance>`.
+///
+/// Example:
+/// ```ignore
+/// create_mock_stp258_currency!(
+///     Tokens, AccountId, CurrencyId, Balance, Amount, BlockNumber,
+///     ExistentialDeposits, BaseUnits, ()
+/// );
+/// ```
+#[macro_export]
+macro_rules! create_mock_stp258_currency {
+	(
+		$name:ident,
+		$account_id:ty,
+		$currency_id:ty,
+		$balance:ty,
+		$amount:ty,
+		$moment:ty,
+		$existential_deposits:ty,
+		$base_units:ty,
+		$on_dust:ty
+	) => {
+		pub struct $name;
+
+		impl $name {
+			fn with_ledger<R>(
+				f: impl FnOnce(
+					&mut $crate::mock::Ledger<$account_id, $currency_id, $balance, $existential_deposits>,
+				) -> R,
+			) -> R {
+				thread_local! {
+					static LEDGER: ::std::cell::RefCell<
+						$crate::mock::Ledger<$account_id, $currency_id, $balance, $existential_deposits>
+					> = ::std::cell::RefCell::new(Default::default());
+				}
+
+				let (result, dust) = LEDGER.with(|ledger| {
+					let mut ledger = ledger.borrow_mut();
+					let result = f(&mut ledger);
+					(result, ledger.take_dust())
+				});
+				// `OnDust` may call back into the ledger, so it is only invoked
+				// once the ledger is released.
+				for (who, currency_id, amount) in dust {
+					<$on_dust as $crate::OnDust<$account_id, $currency_id, $balance>>::on_dust(
+						&who,
+						currency_id,
+						amount,
+					);
+				}
+				result
+			}
+		}
+
+		impl $crate::Stp258Currency<$account_id> for $name {
+			type CurrencyId = $currency_id;
+			type Balance = $balance;
+
+			fn minimum_balance(currency_id: Self::CurrencyId) -> Self::Balance {
+				<$existential_deposits as $crate::GetByKey<$currency_id, $balance>>::get(&currency_id)
+			}
+
+			fn base_unit(currency_id: Self::CurrencyId) -> Self::Balance {
+				<$base_units as $crate::GetByKey<$currency_id, $balance>>::get(&currency_id)
+			}
+
+			fn total_issuance(currency_id: Self::CurrencyId) -> Self::Balance {
+				Self::with_ledger(|ledger| ledger.total_issuance(currency_id))
+			}
+
+			fn total_balance(currency_id: Self::CurrencyId, who: &$account_id) -> Self::Balance {
+				Self::with_ledger(|ledger| ledger.total_balance(currency_id, who))
+			}
+
+			fn free_balance(currency_id: Self::CurrencyId, who: &$account_id) -> Self::Balance {
+				Self::with_ledger(|ledger| ledger.free_balance(currency_id, who))
+			}
+
+			fn ensure_can_withdraw(
+				currency_id: Self::CurrencyId,
+				who: &$account_id,
+				amount: Self::Balance,
+			) -> sp_runtime::DispatchResult {
+				Self::with_ledger(|ledger| ledger.ensure_can_withdraw(currency_id, who, amount))
+			}
+
+			fn transfer(
+				currency_id: Self::CurrencyId,
+				from: &$account_id,
+				to: &$account_id,
+				amount: Self::Balance,
+			) -> sp_runtime::DispatchResult {
+				Self::with_ledger(|ledger| ledger.transfer(currency_id, from, to, amount))
+			}
+
+			fn deposit(
+				currency_id: Self::CurrencyId,
+				who: &$account_id,
+				amount: Self::Balance,
+			) -> sp_runtime::DispatchResult {
+				Self::with_ledger(|ledger| ledger.deposit(currency_id, who, amount))
+			}
+
+			fn withdraw(
+				currency_id: Self::CurrencyId,
+				who: &$account_id,
+				amount: Self::Balance,
+			) -> sp_runtime::DispatchResult {
+				Self::with_ledger(|ledger| ledger.withdraw(currency_id, who, amount))
+			}
+
+			fn can_slash(currency_id: Self::CurrencyId, who: &$account_id, value: Self::Balance) -> bool {
+				Self::with_ledger(|ledger| ledger.can_slash(currency_id, who, value))
+			}
+
+			fn slash(currency_id: Self::CurrencyId, who: &$account_id, amount: Self::Balance) -> Self::Balance {
+				Self::with_ledger(|ledger| ledger.slash(currency_id, who, amount))
+			}
+		}
+
+		impl $crate::Stp258CurrencyExtended<$account_id> for $name {
+			type Amount = $amount;
+
+			fn update_balance(
+				currency_id: Self::CurrencyId,
+				who: &$account_id,
+				by_amount: Self::Amount,
+			) -> sp_runtime::DispatchResult {
+				if <$amount as $crate::arithmetic::Zero>::is_zero(&by_amount) {
+					return Ok(());
+				}
+				if by_amount == <$amount as $crate::arithmetic::Bounded>::min_value() {
+					return Err($crate::mock::Error::AmountIntoBalanceFailed.into());
+				}
+
+				let by_balance: $balance = sp_std::convert::TryInto::try_into(
+					<$amount as $crate::arithmetic::Signed>::abs(&by_amount),
+				)
+				.map_err(|_| $crate::mock::Error::AmountIntoBalanceFailed)?;
+				if <$amount as $crate::arithmetic::Signed>::is_positive(&by_amount) {
+					<Self as $crate::Stp258Currency<$account_id>>::deposit(currency_id, who, by_balance)
+				} else {
+					<Self as $crate::Stp258Currency<$account_id>>::withdraw(currency_id, who, by_balance)
+				}
+			}
+		}
+
+		impl $crate::Stp258CurrencyLockable<$account_id> for $name {
+			type Moment = $moment;
+
+			fn set_lock(
+				lock_id: $crate::LockIdentifier,
+				currency_id: Self::CurrencyId,
+				who: &$account_id,
+				amount: Self::Balance,
+			) -> sp_runtime::DispatchResult {
+				Self::with_ledger(|ledger| ledger.set_lock(lock_id, currency_id, who, amount));
+				Ok(())
+			}
+
+			fn extend_lock(
+				lock_id: $crate::LockIdentifier,
+				currency_id: Self::CurrencyId,
+				who: &$account_id,
+				amount: Self::Balance,
+			) -> sp_runtime::DispatchResult {
+				Self::with_ledger(|ledger| ledger.extend_lock(lock_id, currency_id, who, amount));
+				Ok(())
+			}
+
+			fn remove_lock(
+				lock_id: $crate::LockIdentifier,
+				currency_id: Self::CurrencyId,
+				who: &$account_id,
+			) -> sp_runtime::DispatchResult {
+				Self::with_ledger(|ledger| ledger.remove_lock(lock_id, currency_id, who));
+				Ok(())
+			}
+		}
+
+		impl $crate::Stp258CurrencyReservable<$account_id> for $name {
+			fn can_reserve(currency_id: Self::CurrencyId, who: &$account_id, value: Self::Balance) -> bool {
+				Self::with_ledger(|ledger| ledger.can_reserve(currency_id, who, value))
+			}
+
+			fn slash_reserved(currency_id: Self::CurrencyId, who: &$account_id, value: Self::Balance) -> Self::Balance {
+				Self::with_ledger(|ledger| ledger.slash_reserved(currency_id, who, value))
+			}
+
+			fn reserved_balance(currency_id: Self::CurrencyId, who: &$account_id) -> Self::Balance {
+				Self::with_ledger(|ledger| ledger.reserved_balance(currency_id, who))
+			}
+
+			fn reserve(
+				currency_id: Self::CurrencyId,
+				who: &$account_id,
+				value: Self::Balance,
+			) -> sp_runtime::DispatchResult {
+				Self::with_ledger(|ledger| ledger.reserve(currency_id, who, value))
+			}
+
+			fn unreserve(currency_id: Self::CurrencyId, who: &$account_id, value: Self::Balance) -> Self::Balance {
+				Self::with_ledger(|ledger| ledger.unreserve(currency_id, who, value))
+			}
+
+			fn repatriate_reserved(
+				currency_id: Self::CurrencyId,
+				slashed: &$account_id,
+				beneficiary: &$account_id,
+				value: Self::Balance,
+				status: $crate::BalanceStatus,
+			) -> sp_std::result::Result<Self::Balance, sp_runtime::DispatchError> {
+				Self::with_ledger(|ledger| ledger.repatriate_reserved(currency_id, slashed, beneficiary, value, status))
+			}
+		}
+
+		impl $crate::mock::AdjustIssuance<$currency_id, $balance> for $name {
+			fn increase_issuance(currency_id: $currency_id, amount: $balance) {
+				Self::with_ledger(|ledger| {
+					ledger.issue(currency_id, amount);
+				});
+			}
+
+			fn decrease_issuance(currency_id: $currency_id, amount: $balance) {
+				Self::with_ledger(|ledger| {
+					ledger.burn(currency_id, amount);
+				});
+			}
+		}
+
+		impl $crate::Stp258CurrencyImbalanced<$account_id> for $name {
+			type PositiveImbalance = $crate::mock::PositiveImbalance<$currency_id, $balance, $name>;
+			type NegativeImbalance = $crate::mock::NegativeImbalance<$currency_id, $balance, $name>;
+
+			fn burn(currency_id: Self::CurrencyId, amount: Self::Balance) -> Self::PositiveImbalance {
+				let amount = Self::with_ledger(|ledger| ledger.burn(currency_id, amount));
+				$crate::mock::PositiveImbalance::new(currency_id, amount)
+			}
+
+			fn issue(currency_id: Self::CurrencyId, amount: Self::Balance) -> Self::NegativeImbalance {
+				let amount = Self::with_ledger(|ledger| ledger.issue(currency_id, amount));
+				$crate::mock::NegativeImbalance::new(currency_id, amount)
+			}
+
+			fn deposit_into_existing(
+				currency_id: Self::CurrencyId,
+				who: &$account_id,
+				value: Self::Balance,
+			) -> sp_std::result::Result<Self::PositiveImbalance, sp_runtime::DispatchError> {
+				Self::with_ledger(|ledger| ledger.deposit_into_existing(currency_id, who, value))?;
+				Ok($crate::mock::PositiveImbalance::new(currency_id, value))
+			}
+
+			fn deposit_creating(
+				currency_id: Self::CurrencyId,
+				who: &$account_id,
+				value: Self::Balance,
+			) -> Self::PositiveImbalance {
+				let value = Self::with_ledger(|ledger| ledger.deposit_creating(currency_id, who, value));
+				$crate::mock::PositiveImbalance::new(currency_id, value)
+			}
+
+			fn withdraw_imbalance(
+				currency_id: Self::CurrencyId,
+				who: &$account_id,
+				value: Self::Balance,
+			) -> sp_std::result::Result<Self::NegativeImbalance, sp_runtime::DispatchError> {
+				Self::with_ledger(|ledger| ledger.withdraw_imbalance(currency_id, who, value))?;
+				Ok($crate::mock::NegativeImbalance::new(currency_id, value))
+			}
+		}
+	};
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{
+		parameter_type_with_key, BalanceStatus, LockIdentifier, OnDust, Stp258Currency, Stp258CurrencyExtended,
+		Stp258CurrencyImbalanced, Stp258CurrencyLockable, Stp258CurrencyReservable,
+	};
+	use frame_support::traits::Imbalance;
+	use sp_runtime::DispatchError;
+	use sp_std::cell::RefCell;
+
+	const ALICE: u64 = 1;
+	const BOB: u64 = 2;
+	const SETT: u32 = 0;
+	const DNAR: u32 = 1;
+	const ID_1: LockIdentifier = *b"1       ";
+	const ID_2: LockIdentifier = *b"2       ";
+
+	parameter_type_with_key! {
+		pub ExistentialDeposits: |currency_id: u32| -> u64 {
+			match currency_id {
+				&SETT => 2,
+				_ => 0,
+			}
+		};
+	}
+
+	parameter_type_with_key! {
+		pub BaseUnits: |_currency_id: u32| -> u64 {
+			1_000
+		};
+	}
+
+	thread_local! {
+		static DUST: RefCell<Vec<(u64, u32, u64)>> = RefCell::new(vec![]);
+	}
+
+	pub struct RecordDust;
+	impl OnDust<u64, u32, u64> for RecordDust {
+		fn on_dust(who: &u64, currency_id: u32, amount: u64) {
+			DUST.with(|v| v.borrow_mut().push((*who, currency_id, amount)));
+		}
+	}
+
+	create_mock_stp258_currency!(
+		Tokens,
+		u64,
+		u32,
+		u64,
+		i64,
+		u64,
+		ExistentialDeposits,
+		BaseUnits,
+		RecordDust
+	);
+
+	fn dust() -> Vec<(u64, u32, u64)> {
+		DUST.with(|v| v.borrow().clone())
+	}
+
+	#[test]
+	fn deposit_and_withdraw_should_work() {
+		assert_eq!(Tokens::deposit(SETT, &ALICE, 100), Ok(()));
+		assert_eq!(Tokens::free_balance(SETT, &ALICE), 100);
+		assert_eq!(Tokens::total_issuance(SETT), 100);
+
+		assert_eq!(Tokens::withdraw(SETT, &ALICE, 40), Ok(()));
+		assert_eq!(Tokens::free_balance(SETT, &ALICE), 60);
+		assert_eq!(Tokens::total_issuance(SETT), 60);
+
+		assert_eq!(
+			Tokens::withdraw(SETT, &ALICE, 61),
+			Err(DispatchError::Other("BalanceTooLow"))
+		);
+		assert_eq!(Tokens::deposit(SETT, &ALICE, u64::max_value()), Err(DispatchError::Other("TotalIssuanceOverflow")));
+		assert_eq!(Tokens::minimum_balance(SETT), 2);
+		assert_eq!(Tokens::base_unit(SETT), 1_000);
+	}
+
+	#[test]
+	fn existential_deposit_should_be_enforced() {
+		assert_eq!(
+			Tokens::deposit(SETT, &ALICE, 1),
+			Err(DispatchError::Other("ExistentialDeposit"))
+		);
+		assert_eq!(Tokens::deposit(SETT, &ALICE, 10), Ok(()));
+		assert_eq!(
+			Tokens::transfer(SETT, &ALICE, &BOB, 1),
+			Err(DispatchError::Other("ExistentialDeposit"))
+		);
+		assert_eq!(Tokens::transfer(SETT, &ALICE, &BOB, 2), Ok(()));
+		assert_eq!(Tokens::free_balance(SETT, &BOB), 2);
+
+		// no existential deposit for `DNAR`
+		assert_eq!(Tokens::deposit(DNAR, &ALICE, 1), Ok(()));
+	}
+
+	#[test]
+	fn reaped_account_should_call_on_dust() {
+		assert_eq!(Tokens::deposit(SETT, &ALICE, 10), Ok(()));
+		assert_eq!(Tokens::transfer(SETT, &ALICE, &BOB, 9), Ok(()));
+
+		assert_eq!(Tokens::total_balance(SETT, &ALICE), 0);
+		assert_eq!(Tokens::total_issuance(SETT), 9);
+		assert_eq!(dust(), vec![(ALICE, SETT, 1)]);
+	}
+
+	#[test]
+	fn update_balance_should_work() {
+		assert_eq!(Tokens::update_balance(SETT, &ALICE, 10), Ok(()));
+		assert_eq!(Tokens::free_balance(SETT, &ALICE), 10);
+		assert_eq!(Tokens::update_balance(SETT, &ALICE, -4), Ok(()));
+		assert_eq!(Tokens::free_balance(SETT, &ALICE), 6);
+		assert_eq!(
+			Tokens::update_balance(SETT, &ALICE, i64::min_value()),
+			Err(DispatchError::Other("AmountIntoBalanceFailed"))
+		);
+	}
+
+	#[test]
+	fn locks_should_restrict_withdrawal() {
+		assert_eq!(Tokens::deposit(SETT, &ALICE, 100), Ok(()));
+		assert_eq!(Tokens::set_lock(ID_1, SETT, &ALICE, 50), Ok(()));
+		assert_eq!(Tokens::set_lock(ID_2, SETT, &ALICE, 30), Ok(()));
+		assert_eq!(
+			Tokens::withdraw(SETT, &ALICE, 51),
+			Err(DispatchError::Other("LiquidityRestrictions"))
+		);
+		assert_eq!(Tokens::ensure_can_withdraw(SETT, &ALICE, 50), Ok(()));
+
+		// `extend_lock` keeps the larger amount, `set_lock` replaces it
+		assert_eq!(Tokens::extend_lock(ID_1, SETT, &ALICE, 40), Ok(()));
+		assert!(Tokens::ensure_can_withdraw(SETT, &ALICE, 51).is_err());
+		assert_eq!(Tokens::set_lock(ID_1, SETT, &ALICE, 20), Ok(()));
+		assert_eq!(Tokens::ensure_can_withdraw(SETT, &ALICE, 70), Ok(()));
+		assert!(Tokens::ensure_can_withdraw(SETT, &ALICE, 71).is_err());
+
+		assert_eq!(Tokens::remove_lock(ID_2, SETT, &ALICE), Ok(()));
+		assert_eq!(Tokens::ensure_can_withdraw(SETT, &ALICE, 80), Ok(()));
+		assert_eq!(Tokens::remove_lock(ID_1, SETT, &ALICE), Ok(()));
+		assert_eq!(Tokens::withdraw(SETT, &ALICE, 100), Ok(()));
+	}
+
+	#[test]
+	fn slash_should_take_free_then_reserved() {
+		assert_eq!(Tokens::deposit(SETT, &ALICE, 100), Ok(()));
+		assert_eq!(Tokens::reserve(SETT, &ALICE, 30), Ok(()));
+
+		assert!(Tokens::can_slash(SETT, &ALICE, 70));
+		assert!(!Tokens::can_slash(SETT, &ALICE, 71));
+		assert_eq!(Tokens::slash(SETT, &ALICE, 80), 0);
+		assert_eq!(Tokens::free_balance(SETT, &ALICE), 0);
+		assert_eq!(Tokens::reserved_balance(SETT, &ALICE), 20);
+		assert_eq!(Tokens::slash(SETT, &ALICE, 30), 10);
+		assert_eq!(Tokens::total_issuance(SETT), 0);
+	}
+
+	#[test]
+	fn reserve_and_unreserve_should_work() {
+		assert_eq!(Tokens::deposit(SETT, &ALICE, 100), Ok(()));
+		assert!(!Tokens::can_reserve(SETT, &ALICE, 101));
+		assert_eq!(
+			Tokens::reserve(SETT, &ALICE, 101),
+			Err(DispatchError::Other("BalanceTooLow"))
+		);
+		assert_eq!(Tokens::reserved_balance(SETT, &ALICE), 0);
+
+		assert_eq!(Tokens::reserve(SETT, &ALICE, 60), Ok(()));
+		assert_eq!(Tokens::free_balance(SETT, &ALICE), 40);
+		assert_eq!(Tokens::total_balance(SETT, &ALICE), 100);
+		assert_eq!(Tokens::unreserve(SETT, &ALICE, 70), 10);
+		assert_eq!(Tokens::free_balance(SETT, &ALICE), 100);
+
+		assert_eq!(Tokens::reserve(SETT, &ALICE, 60), Ok(()));
+		assert_eq!(Tokens::slash_reserved(SETT, &ALICE, 70), 10);
+		assert_eq!(Tokens::total_issuance(SETT), 40);
+	}
+
+	#[test]
+	fn repatriate_reserved_should_work() {
+		assert_eq!(Tokens::deposit(SETT, &ALICE, 100), Ok(()));
+		assert_eq!(Tokens::reserve(SETT, &ALICE, 50), Ok(()));
+
+		assert_eq!(
+			Tokens::repatriate_reserved(SETT, &ALICE, &BOB, 10, BalanceStatus::Free),
+			Err(DispatchError::Other("DeadAccount"))
+		);
+
+		assert_eq!(Tokens::deposit(SETT, &BOB, 10), Ok(()));
+		assert_eq!(
+			Tokens::repatriate_reserved(SETT, &ALICE, &BOB, 20, BalanceStatus::Free),
+			Ok(0)
+		);
+		assert_eq!(
+			Tokens::repatriate_reserved(SETT, &ALICE, &BOB, 40, BalanceStatus::Reserved),
+			Ok(10)
+		);
+		assert_eq!(Tokens::free_balance(SETT, &BOB), 30);
+		assert_eq!(Tokens::reserved_balance(SETT, &BOB), 30);
+		assert_eq!(Tokens::reserved_balance(SETT, &ALICE), 0);
+		assert_eq!(Tokens::total_issuance(SETT), 110);
+
+		assert_eq!(
+			Tokens::repatriate_reserved(SETT, &BOB, &BOB, 40, BalanceStatus::Reserved),
+			Ok(10)
+		);
+		assert_eq!(
+			Tokens::repatriate_reserved(SETT, &BOB, &BOB, 40, BalanceStatus::Free),
+			Ok(10)
+		);
+		assert_eq!(Tokens::free_balance(SETT, &BOB), 60);
+	}
+
+	#[test]
+	fn imbalances_should_adjust_issuance_when_dropped() {
+		let issued = Tokens::issue(SETT, 100);
+		assert_eq!(Tokens::total_issuance(SETT), 100);
+
+		let (first, second) = issued.split(30);
+		assert_eq!((first.peek(), second.peek()), (30, 70));
+		drop(first);
+		assert_eq!(Tokens::total_issuance(SETT), 70);
+
+		let burned = Tokens::burn(SETT, 50);
+		assert_eq!(Tokens::total_issuance(SETT), 20);
+		let remainder = match second.offset(burned) {
+			Ok(remainder) => remainder,
+			Err(_) => panic!("the negative imbalance is the larger one"),
+		};
+		assert_eq!(remainder.peek(), 20);
+		assert_eq!(Tokens::total_issuance(SETT), 20);
+
+		let mut merged = <Tokens as Stp258CurrencyImbalanced<u64>>::NegativeImbalance::zero();
+		assert_eq!(merged.currency_id(), None);
+		merged.subsume(remainder);
+		assert_eq!(merged.currency_id(), Some(SETT));
+		drop(merged);
+		assert_eq!(Tokens::total_issuance(SETT), 0);
+	}
+}
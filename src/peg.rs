@@ -0,0 +1,69 @@
+use crate::GetByKey;
+use sp_runtime::traits::One;
+use sp_std::marker::PhantomData;
+
+/// A trait to provide the target peg price of a stable currency, quoted in
+/// the same unit as the prices handed to `SerpTes`.
+pub trait PegProvider<CurrencyId, Price> {
+	/// The peg of `currency_id`, or `None` if it is not pegged.
+	fn get_peg(currency_id: CurrencyId) -> Option<Price>;
+}
+
+/// Every currency is pegged 1:1.
+impl<CurrencyId, Price: One> PegProvider<CurrencyId, Price> for () {
+	fn get_peg(_currency_id: CurrencyId) -> Option<Price> {
+		Some(Price::one())
+	}
+}
+
+/// A `PegProvider` reading the peg of every currency from `Pegs`, typically
+/// declared with `parameter_type_with_key!`.
+pub struct PegFromKey<Pegs>(PhantomData<Pegs>);
+
+impl<CurrencyId, Price, Pegs> PegProvider<CurrencyId, Price> for PegFromKey<Pegs>
+where
+	Pegs: GetByKey<CurrencyId, Price>,
+{
+	fn get_peg(currency_id: CurrencyId) -> Option<Price> {
+		Some(Pegs::get(&currency_id))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::parameter_type_with_key;
+	use sp_runtime::{FixedPointNumber, FixedU128};
+
+	const USD: u32 = 0;
+	const EUR: u32 = 1;
+
+	parameter_type_with_key! {
+		pub Pegs: |currency_id: u32| -> FixedU128 {
+			match currency_id {
+				&EUR => FixedU128::saturating_from_rational(11, 10),
+				_ => FixedU128::saturating_from_integer(1),
+			}
+		};
+	}
+
+	#[test]
+	fn unit_peg_provider_should_work() {
+		assert_eq!(
+			<() as PegProvider<u32, FixedU128>>::get_peg(EUR),
+			Some(FixedU128::saturating_from_integer(1))
+		);
+	}
+
+	#[test]
+	fn peg_from_key_should_work() {
+		assert_eq!(
+			PegFromKey::<Pegs>::get_peg(EUR),
+			Some(FixedU128::saturating_from_rational(11, 10))
+		);
+		assert_eq!(
+			PegFromKey::<Pegs>::get_peg(USD),
+			Some(FixedU128::saturating_from_integer(1))
+		);
+	}
+}
@@ -1,34 +1,243 @@
-use crate::DataProvider;
-use frame_support::Parameter;
-use sp_runtime::traits::{CheckedDiv, MaybeSerializeDeserialize, Member};
-use sp_std::marker::PhantomData;
+use crate::{DataProvider, DataProviderExtended, FetchCurrencyPrice, TimestampedValue};
+use codec::{Decode, Encode};
+use frame_support::{
+	traits::{Get, Time},
+	Parameter,
+};
+use sp_runtime::{
+	traits::{CheckedDiv, CheckedMul, MaybeSerializeDeserialize, Member, One, Saturating, Zero},
+	DispatchError, RuntimeDebug,
+};
+use sp_std::{marker::PhantomData, prelude::*, result};
 
 /// A trait to provide relative price for two currencies
 pub trait PriceProvider<CurrencyId, Price> {
 	fn get_price(base: CurrencyId, quote: CurrencyId) -> Option<Price>;
 }
 
+/// The reason a relative price is not available.
+#[derive(Encode, Decode, Clone, Copy, Eq, PartialEq, RuntimeDebug)]
+pub enum PriceError {
+	/// No price for the base currency.
+	MissingBase,
+	/// No price for the quote currency.
+	MissingQuote,
+	/// The price of the quote currency is zero.
+	ZeroQuote,
+	/// The price of the base or quote currency is too old.
+	Stale,
+	/// The relative price overflows.
+	Overflow,
+	/// No chain of price feeds links the base to the quote currency.
+	NoRoute,
+}
+
+impl From<PriceError> for DispatchError {
+	fn from(e: PriceError) -> Self {
+		match e {
+			PriceError::MissingBase => DispatchError::Other("MissingBasePrice"),
+			PriceError::MissingQuote => DispatchError::Other("MissingQuotePrice"),
+			PriceError::ZeroQuote => DispatchError::Other("ZeroQuotePrice"),
+			PriceError::Stale => DispatchError::Other("StalePrice"),
+			PriceError::Overflow => DispatchError::Other("PriceOverflow"),
+			PriceError::NoRoute => DispatchError::Other("NoPriceRoute"),
+		}
+	}
+}
+
+/// A trait to provide relative price for two currencies, or the reason it is
+/// not available
+pub trait TryPriceProvider<CurrencyId, Price> {
+	fn try_get_price(base: CurrencyId, quote: CurrencyId) -> result::Result<Price, PriceError>;
+}
+
+impl<CurrencyId, Price, T: TryPriceProvider<CurrencyId, Price>> PriceProvider<CurrencyId, Price> for T {
+	fn get_price(base: CurrencyId, quote: CurrencyId) -> Option<Price> {
+		T::try_get_price(base, quote).ok()
+	}
+}
+
+fn checked_relative_price<Price: CheckedDiv + Zero>(base: Price, quote: Price) -> result::Result<Price, PriceError> {
+	if quote.is_zero() {
+		return Err(PriceError::ZeroQuote);
+	}
+	base.checked_div(&quote).ok_or(PriceError::Overflow)
+}
+
 /// A `PriceProvider` implementation based on price data from a `DataProvider`
 pub struct DefaultPriceProvider<CurrencyId, Source>(PhantomData<(CurrencyId, Source)>);
 
-impl<CurrencyId, Source, Price> PriceProvider<CurrencyId, Price> for DefaultPriceProvider<CurrencyId, Source>
+impl<CurrencyId, Source, Price> TryPriceProvider<CurrencyId, Price> for DefaultPriceProvider<CurrencyId, Source>
 where
 	CurrencyId: Parameter + Member + Copy + MaybeSerializeDeserialize,
 	Source: DataProvider<CurrencyId, Price>,
-	Price: CheckedDiv,
+	Price: CheckedDiv + Zero,
 {
-	fn get_price(base_currency_id: CurrencyId, quote_currency_id: CurrencyId) -> Option<Price> {
-		let base_price = Source::get(&base_currency_id)?;
-		let quote_price = Source::get(&quote_currency_id)?;
+	fn try_get_price(
+		base_currency_id: CurrencyId,
+		quote_currency_id: CurrencyId,
+	) -> result::Result<Price, PriceError> {
+		let base_price = Source::get(&base_currency_id).ok_or(PriceError::MissingBase)?;
+		let quote_price = Source::get(&quote_currency_id).ok_or(PriceError::MissingQuote)?;
+
+		checked_relative_price(base_price, quote_price)
+	}
+}
+
+/// A `PriceProvider` implementation based on timestamped price data from a
+/// `DataProviderExtended`, failing with `Stale` if either price is older than
+/// `MaxAge`
+pub struct FreshPriceProvider<Source, Now, MaxAge>(PhantomData<(Source, Now, MaxAge)>);
+
+impl<Source, Now, MaxAge> FreshPriceProvider<Source, Now, MaxAge>
+where
+	Now: Time,
+	MaxAge: Get<Now::Moment>,
+{
+	fn fresh<CurrencyId, Price>(currency_id: &CurrencyId, missing: PriceError) -> result::Result<Price, PriceError>
+	where
+		Source: DataProviderExtended<CurrencyId, TimestampedValue<Price, Now::Moment>>,
+		Price: Ord,
+	{
+		let TimestampedValue { value, timestamp } = Source::get_no_op(currency_id).ok_or(missing)?;
+		if Now::now().saturating_sub(timestamp) > MaxAge::get() {
+			Err(PriceError::Stale)
+		} else {
+			Ok(value)
+		}
+	}
+}
+
+impl<CurrencyId, Source, Now, MaxAge, Price> TryPriceProvider<CurrencyId, Price>
+	for FreshPriceProvider<Source, Now, MaxAge>
+where
+	CurrencyId: Parameter + Member + Copy + MaybeSerializeDeserialize,
+	Source: DataProviderExtended<CurrencyId, TimestampedValue<Price, Now::Moment>>,
+	Now: Time,
+	MaxAge: Get<Now::Moment>,
+	Price: CheckedDiv + Zero + Ord,
+{
+	fn try_get_price(
+		base_currency_id: CurrencyId,
+		quote_currency_id: CurrencyId,
+	) -> result::Result<Price, PriceError> {
+		let base_price = Self::fresh(&base_currency_id, PriceError::MissingBase)?;
+		let quote_price = Self::fresh(&quote_currency_id, PriceError::MissingQuote)?;
+
+		checked_relative_price(base_price, quote_price)
+	}
+}
+
+/// A `PriceProvider` implementation composing the pair prices of a
+/// `DataProvider` along the shortest chain of `Pairs` linking base to quote,
+/// of at most `MaxHops` pairs.
+///
+/// The price of the pair `(a, b)` is the price of `a` in `b`; pairs can be
+/// walked both ways, the reverse price being `1 / price`.
+pub struct RoutedPriceProvider<CurrencyId, Source, Pairs, MaxHops>(
+	PhantomData<(CurrencyId, Source, Pairs, MaxHops)>,
+);
+
+impl<CurrencyId, Source, Pairs, MaxHops, Price> TryPriceProvider<CurrencyId, Price>
+	for RoutedPriceProvider<CurrencyId, Source, Pairs, MaxHops>
+where
+	CurrencyId: Parameter + Member + Copy + MaybeSerializeDeserialize,
+	Source: DataProvider<(CurrencyId, CurrencyId), Price>,
+	Pairs: Get<Vec<(CurrencyId, CurrencyId)>>,
+	MaxHops: Get<u32>,
+	Price: CheckedMul + CheckedDiv + Zero + One,
+{
+	fn try_get_price(
+		base_currency_id: CurrencyId,
+		quote_currency_id: CurrencyId,
+	) -> result::Result<Price, PriceError> {
+		if base_currency_id == quote_currency_id {
+			return Ok(Price::one());
+		}
+
+		let pairs = Pairs::get();
+		let mut visited = vec![base_currency_id];
+		// currencies reached by the last hop, with the price of base in them
+		let mut frontier = vec![(base_currency_id, Price::one())];
+		let mut overflow = false;
+
+		for _ in 0..MaxHops::get() {
+			let mut next = Vec::new();
+			for (from, price) in frontier {
+				for &(a, b) in pairs.iter() {
+					let (to, rate) = if a == from {
+						match Source::get(&(a, b)) {
+							Some(rate) => (b, rate),
+							None => continue,
+						}
+					} else if b == from {
+						match Source::get(&(a, b)).filter(|rate| !rate.is_zero()) {
+							Some(rate) => match Price::one().checked_div(&rate) {
+								Some(rate) => (a, rate),
+								None => {
+									overflow = true;
+									continue;
+								}
+							},
+							None => continue,
+						}
+					} else {
+						continue;
+					};
+					if visited.contains(&to) {
+						continue;
+					}
+
+					match price.checked_mul(&rate) {
+						Some(price) if to == quote_currency_id => return Ok(price),
+						Some(price) => {
+							visited.push(to);
+							next.push((to, price));
+						}
+						None => overflow = true,
+					}
+				}
+			}
+			if next.is_empty() {
+				break;
+			}
+			frontier = next;
+		}
 
-		base_price.checked_div(&quote_price)
+		Err(if overflow {
+			PriceError::Overflow
+		} else {
+			PriceError::NoRoute
+		})
+	}
+}
+
+/// A `FetchCurrencyPrice` implementation quoting every currency in `Quote`,
+/// based on price data from a `DataProvider`
+pub struct DataProviderFetchPrice<Source, CurrencyId, Quote>(PhantomData<(Source, CurrencyId, Quote)>);
+
+impl<Source, CurrencyId, Quote, Price> FetchCurrencyPrice<CurrencyId, Price>
+	for DataProviderFetchPrice<Source, CurrencyId, Quote>
+where
+	CurrencyId: Parameter + Member + Copy + MaybeSerializeDeserialize,
+	Source: DataProvider<CurrencyId, Price>,
+	Quote: Get<CurrencyId>,
+	Price: CheckedDiv + Zero,
+{
+	fn fetch_price(currency_id: CurrencyId) -> Option<Price> {
+		DefaultPriceProvider::<CurrencyId, Source>::get_price(currency_id, Quote::get())
 	}
 }
 
 #[cfg(test)]	
 mod test {
 	use super::*;
+	use frame_support::parameter_types;
 	use sp_runtime::{FixedPointNumber, FixedU128};
+	use sp_std::{
+		convert::TryInto,
+		ops::{Add, Div, Mul},
+	};
 
 	type Price = FixedU128;
 
@@ -46,6 +255,49 @@ mod test {
 
 	type TestPriceProvider = DefaultPriceProvider<u32, MockDataProvider>;
 
+	parameter_types! {
+		pub const QuoteCurrency: u32 = 2;
+	}
+
+	type TestFetchPrice = DataProviderFetchPrice<MockDataProvider, u32, QuoteCurrency>;
+
+	pub struct MockTimestampedDataProvider;
+	impl DataProviderExtended<u32, TimestampedValue<Price, u64>> for MockTimestampedDataProvider {
+		fn get_no_op(currency: &u32) -> Option<TimestampedValue<Price, u64>> {
+			let (value, timestamp) = match currency {
+				0 => (0, 100),
+				1 => (1, 80),
+				2 => (2, 100),
+				3 => (3, 79),
+				4 => (4, 120),
+				_ => return None,
+			};
+			Some(TimestampedValue {
+				value: Price::from_inner(value),
+				timestamp,
+			})
+		}
+
+		fn get_all_values() -> Vec<(u32, Option<TimestampedValue<Price, u64>>)> {
+			(0..5).map(|k| (k, Self::get_no_op(&k))).collect()
+		}
+	}
+
+	pub struct MockTime;
+	impl Time for MockTime {
+		type Moment = u64;
+
+		fn now() -> u64 {
+			100
+		}
+	}
+
+	parameter_types! {
+		pub const MaxAge: u64 = 20;
+	}
+
+	type TestFreshPriceProvider = FreshPriceProvider<MockTimestampedDataProvider, MockTime, MaxAge>;
+
 	#[test]
 	fn get_price_should_work() {
 		assert_eq!(
@@ -71,4 +323,233 @@ mod test {
 		assert_eq!(TestPriceProvider::get_price(1, 0), None);
 		assert_eq!(TestPriceProvider::get_price(0, 1), Some(Price::from_inner(0)));
 	}
+
+	#[test]
+	fn try_get_price_should_tell_why_price_is_missing() {
+		assert_eq!(
+			TestPriceProvider::try_get_price(1, 2),
+			Ok(Price::saturating_from_rational(1, 2))
+		);
+		assert_eq!(TestPriceProvider::try_get_price(3, 1), Err(PriceError::MissingBase));
+		assert_eq!(TestPriceProvider::try_get_price(1, 3), Err(PriceError::MissingQuote));
+		assert_eq!(TestPriceProvider::try_get_price(1, 0), Err(PriceError::ZeroQuote));
+		assert_eq!(TestPriceProvider::try_get_price(0, 1), Ok(Price::from_inner(0)));
+	}
+
+	#[test]
+	fn try_get_price_should_fail_on_overflow() {
+		pub struct LargeDataProvider;
+		impl DataProvider<u32, Price> for LargeDataProvider {
+			fn get(currency: &u32) -> Option<Price> {
+				match currency {
+					0 => Some(Price::from_inner(u128::max_value())),
+					_ => Some(Price::from_inner(1)),
+				}
+			}
+		}
+
+		assert_eq!(
+			DefaultPriceProvider::<u32, LargeDataProvider>::try_get_price(0, 1),
+			Err(PriceError::Overflow)
+		);
+		assert_eq!(DefaultPriceProvider::<u32, LargeDataProvider>::get_price(0, 1), None);
+	}
+
+	const SETT: u32 = 10;
+	const USD: u32 = 11;
+	const EUR: u32 = 12;
+	const JPY: u32 = 13;
+	const DNAR: u32 = 14;
+	const HUGE: u32 = 15;
+	const CENT: u32 = 16;
+
+	pub struct MockPairDataProvider;
+	impl DataProvider<(u32, u32), Price> for MockPairDataProvider {
+		fn get(pair: &(u32, u32)) -> Option<Price> {
+			match *pair {
+				(SETT, USD) => Some(Price::saturating_from_integer(2)),
+				(EUR, USD) => Some(Price::saturating_from_rational(5, 4)),
+				(HUGE, USD) => Some(Price::from_inner(u128::max_value() / 2)),
+				(USD, CENT) => Some(Price::saturating_from_integer(100)),
+				_ => None,
+			}
+		}
+	}
+
+	parameter_types! {
+		pub Pairs: Vec<(u32, u32)> = vec![(SETT, USD), (EUR, USD), (JPY, EUR), (HUGE, USD), (USD, CENT)];
+		pub const OneHop: u32 = 1;
+		pub const TwoHops: u32 = 2;
+	}
+
+	type TestRoutedPriceProvider = RoutedPriceProvider<u32, MockPairDataProvider, Pairs, TwoHops>;
+	type TestDirectPriceProvider = RoutedPriceProvider<u32, MockPairDataProvider, Pairs, OneHop>;
+
+	#[test]
+	fn routed_price_provider_should_compose_prices() {
+		assert_eq!(
+			TestRoutedPriceProvider::try_get_price(SETT, USD),
+			Ok(Price::saturating_from_integer(2))
+		);
+		assert_eq!(
+			TestRoutedPriceProvider::try_get_price(USD, SETT),
+			Ok(Price::saturating_from_rational(1, 2))
+		);
+		assert_eq!(
+			TestRoutedPriceProvider::try_get_price(SETT, EUR),
+			Ok(Price::saturating_from_rational(8, 5))
+		);
+		assert_eq!(
+			TestRoutedPriceProvider::try_get_price(EUR, SETT),
+			Ok(Price::saturating_from_rational(5, 8))
+		);
+		assert_eq!(
+			TestRoutedPriceProvider::try_get_price(SETT, SETT),
+			Ok(Price::saturating_from_integer(1))
+		);
+		assert_eq!(
+			TestRoutedPriceProvider::get_price(SETT, CENT),
+			Some(Price::saturating_from_integer(200))
+		);
+	}
+
+	#[test]
+	fn routed_price_provider_should_respect_max_hops() {
+		assert_eq!(
+			TestDirectPriceProvider::try_get_price(SETT, USD),
+			Ok(Price::saturating_from_integer(2))
+		);
+		assert_eq!(TestDirectPriceProvider::try_get_price(SETT, EUR), Err(PriceError::NoRoute));
+	}
+
+	#[test]
+	fn routed_price_provider_should_fail_without_route() {
+		assert_eq!(TestRoutedPriceProvider::try_get_price(SETT, JPY), Err(PriceError::NoRoute));
+		assert_eq!(TestRoutedPriceProvider::try_get_price(SETT, DNAR), Err(PriceError::NoRoute));
+		assert_eq!(TestRoutedPriceProvider::try_get_price(HUGE, CENT), Err(PriceError::Overflow));
+	}
+
+	/// A price in hundredths, overflowing past 2.55.
+	#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+	pub struct Centi(u8);
+
+	impl Add for Centi {
+		type Output = Self;
+
+		fn add(self, rhs: Self) -> Self {
+			Centi(self.0 + rhs.0)
+		}
+	}
+
+	impl Mul for Centi {
+		type Output = Self;
+
+		fn mul(self, rhs: Self) -> Self {
+			self.checked_mul(&rhs).expect("overflow")
+		}
+	}
+
+	impl Div for Centi {
+		type Output = Self;
+
+		fn div(self, rhs: Self) -> Self {
+			self.checked_div(&rhs).expect("overflow")
+		}
+	}
+
+	impl CheckedMul for Centi {
+		fn checked_mul(&self, rhs: &Self) -> Option<Self> {
+			(self.0 as u32 * rhs.0 as u32 / 100).try_into().ok().map(Centi)
+		}
+	}
+
+	impl CheckedDiv for Centi {
+		fn checked_div(&self, rhs: &Self) -> Option<Self> {
+			if rhs.0 == 0 {
+				return None;
+			}
+			(self.0 as u32 * 100 / rhs.0 as u32).try_into().ok().map(Centi)
+		}
+	}
+
+	impl Zero for Centi {
+		fn zero() -> Self {
+			Centi(0)
+		}
+
+		fn is_zero(&self) -> bool {
+			self.0 == 0
+		}
+	}
+
+	impl One for Centi {
+		fn one() -> Self {
+			Centi(100)
+		}
+	}
+
+	pub struct MockCentiDataProvider;
+	impl DataProvider<(u32, u32), Centi> for MockCentiDataProvider {
+		fn get(pair: &(u32, u32)) -> Option<Centi> {
+			match *pair {
+				(SETT, USD) => Some(Centi(1)),
+				(EUR, USD) => Some(Centi(200)),
+				_ => None,
+			}
+		}
+	}
+
+	parameter_types! {
+		pub CentiPairs: Vec<(u32, u32)> = vec![(SETT, USD), (EUR, USD)];
+	}
+
+	#[test]
+	fn routed_price_provider_should_fail_on_reverse_overflow() {
+		type Provider = RoutedPriceProvider<u32, MockCentiDataProvider, CentiPairs, TwoHops>;
+
+		assert_eq!(Provider::try_get_price(SETT, USD), Ok(Centi(1)));
+		assert_eq!(Provider::try_get_price(USD, EUR), Ok(Centi(50)));
+		assert_eq!(Provider::try_get_price(USD, SETT), Err(PriceError::Overflow));
+	}
+
+	#[test]
+	fn fresh_price_provider_should_work() {
+		assert_eq!(
+			TestFreshPriceProvider::get_price(1, 2),
+			Some(Price::saturating_from_rational(1, 2))
+		);
+		assert_eq!(
+			TestFreshPriceProvider::get_price(4, 2),
+			Some(Price::saturating_from_rational(2, 1))
+		);
+		assert_eq!(TestFreshPriceProvider::get_price(1, 0), None);
+		assert_eq!(TestFreshPriceProvider::get_price(0, 1), Some(Price::from_inner(0)));
+		assert_eq!(TestFreshPriceProvider::get_price(1, 5), None);
+	}
+
+	#[test]
+	fn fresh_price_provider_should_reject_stale_prices() {
+		assert_eq!(TestFreshPriceProvider::get_price(3, 2), None);
+		assert_eq!(TestFreshPriceProvider::get_price(2, 3), None);
+		assert_eq!(TestFreshPriceProvider::try_get_price(3, 2), Err(PriceError::Stale));
+		assert_eq!(TestFreshPriceProvider::try_get_price(2, 3), Err(PriceError::Stale));
+		assert_eq!(TestFreshPriceProvider::try_get_price(5, 3), Err(PriceError::MissingBase));
+		assert_eq!(TestFreshPriceProvider::try_get_price(1, 0), Err(PriceError::ZeroQuote));
+	}
+
+	#[test]
+	fn default_price_provider_should_be_serp_tes_price_provider() {
+		assert_eq!(
+			<TestPriceProvider as crate::SerpTesPriceProvider<u32, Price>>::get_price(1, 2),
+			TestPriceProvider::get_price(1, 2)
+		);
+	}
+
+	#[test]
+	fn fetch_price_should_work() {
+		assert_eq!(TestFetchPrice::fetch_price(1), Some(Price::saturating_from_rational(1, 2)));
+		assert_eq!(TestFetchPrice::fetch_price(2), Some(Price::saturating_from_integer(1)));
+		assert_eq!(TestFetchPrice::fetch_price(0), Some(Price::from_inner(0)));
+		assert_eq!(TestFetchPrice::fetch_price(3), None);
+	}
 }
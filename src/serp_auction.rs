@@ -0,0 +1,645 @@
+use crate::{
+	serp_supply::{mul_ratio, Rounding},
+	Auction, AuctionHandler, Change, OnNewBidResult, OnSerpAdjustment, SerpAdjustment, SerpDirection, SerpMarket,
+	Stp258Currency, Stp258CurrencyReservable,
+};
+use codec::{Decode, Encode};
+use frame_support::traits::Get;
+use sp_runtime::{
+	traits::{AtLeast32BitUnsigned, Saturating, Zero},
+	DispatchError, DispatchResult, RuntimeDebug,
+};
+use sp_std::{
+	convert::{TryFrom, TryInto},
+	marker::PhantomData,
+	result,
+};
+
+/// A serp-down auction: the stable currency burned from the winner, and the
+/// most native currency paid out for it.
+#[derive(Encode, Decode, Clone, Copy, Eq, PartialEq, RuntimeDebug)]
+pub struct SerpDownAuctionItem<CurrencyId, Balance> {
+	/// The currency paid out to the winner.
+	pub native_currency_id: CurrencyId,
+	/// The currency burned once the auction ends.
+	pub stable_currency_id: CurrencyId,
+	/// Amount of native currency paid out to a bid of `stable_amount`,
+	/// proportionally less to higher bids.
+	pub native_amount: Balance,
+	/// Amount of stable currency burned from the winner, and the least a bid
+	/// may be.
+	pub stable_amount: Balance,
+	/// The quote price of the native currency when the auction was opened.
+	pub price: Balance,
+}
+
+/// Storage of the open serp-down auctions.
+pub trait SerpDownAuctionStore<AuctionId, CurrencyId, Balance> {
+	/// The auction `id`, if open.
+	fn get(id: AuctionId) -> Option<SerpDownAuctionItem<CurrencyId, Balance>>;
+	/// Record the opened auction `id`.
+	fn insert(id: AuctionId, item: SerpDownAuctionItem<CurrencyId, Balance>);
+	/// Remove and return the auction `id`.
+	fn take(id: AuctionId) -> Option<SerpDownAuctionItem<CurrencyId, Balance>>;
+}
+
+/// A `SerpMarket` contracting supply through auctions.
+///
+/// `contract_supply` opens a reverse auction on `AuctionModule` lasting
+/// `Duration` blocks, buying back `contract_by` stable currency for at most
+/// `pay_by_quoted` native currency. A bid of `b`, at least `contract_by`,
+/// offers the `contract_by` stable currency for `pay_by_quoted * contract_by
+/// / b` native currency, so that bidders compete by asking for less native
+/// currency. `contract_by` stable currency is reserved on `Market` from the
+/// leading bidder; once the auction ends the native currency is deposited to
+/// the winner, the reserved stable currency burned, and
+/// `OnAdjustment::on_contract` is called.
+///
+/// `contract_supply` only opens the auction, so the `SerpAdjustment` it
+/// returns is pending: nothing is executed yet. `serpers` is not used, as
+/// anyone may bid.
+///
+/// `expand_supply` is forwarded to `Market`, calling `OnAdjustment::on_expand`
+/// on success, and everything else is forwarded to `Market`.
+pub struct SerpDownAuction<BlockNumber, Market, AuctionModule, Store, Now, Duration, OnAdjustment>(
+	PhantomData<(BlockNumber, Market, AuctionModule, Store, Now, Duration, OnAdjustment)>,
+);
+
+/// `lot * part / whole`, rounded down. `None` if `whole` is zero or a value
+/// does not fit in `u128`.
+fn share_of<Balance: AtLeast32BitUnsigned + Copy>(lot: Balance, part: Balance, whole: Balance) -> Option<Balance> {
+	if part == whole {
+		return Some(lot);
+	}
+	let share = mul_ratio(
+		TryInto::<u128>::try_into(lot).ok()?,
+		TryInto::<u128>::try_into(part).ok()?,
+		TryInto::<u128>::try_into(whole).ok()?,
+		Rounding::Down,
+	)?;
+	<Balance as TryFrom<u128>>::try_from(share).ok()
+}
+
+impl<AccountId, BlockNumber, Market, AuctionModule, Store, Now, Duration, OnAdjustment> Stp258Currency<AccountId>
+	for SerpDownAuction<BlockNumber, Market, AuctionModule, Store, Now, Duration, OnAdjustment>
+where
+	Market: Stp258Currency<AccountId>,
+{
+	type CurrencyId = Market::CurrencyId;
+	type Balance = Market::Balance;
+
+	fn minimum_balance(currency_id: Self::CurrencyId) -> Self::Balance {
+		Market::minimum_balance(currency_id)
+	}
+
+	fn base_unit(currency_id: Self::CurrencyId) -> Self::Balance {
+		Market::base_unit(currency_id)
+	}
+
+	fn total_issuance(currency_id: Self::CurrencyId) -> Self::Balance {
+		Market::total_issuance(currency_id)
+	}
+
+	fn total_balance(currency_id: Self::CurrencyId, who: &AccountId) -> Self::Balance {
+		Market::total_balance(currency_id, who)
+	}
+
+	fn free_balance(currency_id: Self::CurrencyId, who: &AccountId) -> Self::Balance {
+		Market::free_balance(currency_id, who)
+	}
+
+	fn ensure_can_withdraw(currency_id: Self::CurrencyId, who: &AccountId, amount: Self::Balance) -> DispatchResult {
+		Market::ensure_can_withdraw(currency_id, who, amount)
+	}
+
+	fn transfer(
+		currency_id: Self::CurrencyId,
+		from: &AccountId,
+		to: &AccountId,
+		amount: Self::Balance,
+	) -> DispatchResult {
+		Market::transfer(currency_id, from, to, amount)
+	}
+
+	fn deposit(currency_id: Self::CurrencyId, who: &AccountId, amount: Self::Balance) -> DispatchResult {
+		Market::deposit(currency_id, who, amount)
+	}
+
+	fn withdraw(currency_id: Self::CurrencyId, who: &AccountId, amount: Self::Balance) -> DispatchResult {
+		Market::withdraw(currency_id, who, amount)
+	}
+
+	fn can_slash(currency_id: Self::CurrencyId, who: &AccountId, value: Self::Balance) -> bool {
+		Market::can_slash(currency_id, who, value)
+	}
+
+	fn slash(currency_id: Self::CurrencyId, who: &AccountId, amount: Self::Balance) -> Self::Balance {
+		Market::slash(currency_id, who, amount)
+	}
+}
+
+impl<AccountId, BlockNumber, Market, AuctionModule, Store, Now, Duration, OnAdjustment> SerpMarket<AccountId>
+	for SerpDownAuction<BlockNumber, Market, AuctionModule, Store, Now, Duration, OnAdjustment>
+where
+	BlockNumber: AtLeast32BitUnsigned + Copy,
+	Market: SerpMarket<AccountId>,
+	AuctionModule: Auction<AccountId, BlockNumber, Balance = Market::Balance>,
+	Store: SerpDownAuctionStore<AuctionModule::AuctionId, Market::CurrencyId, Market::Balance>,
+	Now: Get<BlockNumber>,
+	Duration: Get<BlockNumber>,
+	OnAdjustment: OnSerpAdjustment<Market::CurrencyId, Market::Balance>,
+{
+	fn expand_supply(
+		native_currency_id: Self::CurrencyId,
+		stable_currency_id: Self::CurrencyId,
+		expand_by: Self::Balance,
+		quote_price: Self::Balance,
+		pay_by_quoted: Self::Balance,
+		serpers: &AccountId,
+	) -> result::Result<SerpAdjustment<Self::CurrencyId, Self::Balance>, DispatchError> {
+		let adjustment = Market::expand_supply(
+			native_currency_id,
+			stable_currency_id,
+			expand_by,
+			quote_price,
+			pay_by_quoted,
+			serpers,
+		)?;
+		OnAdjustment::on_expand(&adjustment);
+		Ok(adjustment)
+	}
+
+	fn contract_supply(
+		native_currency_id: Self::CurrencyId,
+		stable_currency_id: Self::CurrencyId,
+		contract_by: Self::Balance,
+		quote_price: Self::Balance,
+		pay_by_quoted: Self::Balance,
+		_serpers: &AccountId,
+	) -> result::Result<SerpAdjustment<Self::CurrencyId, Self::Balance>, DispatchError> {
+		// settled in `on_auction_ended`
+		let now = Now::get();
+		let id = AuctionModule::new_auction(now, Some(now.saturating_add(Duration::get())))?;
+		Store::insert(
+			id,
+			SerpDownAuctionItem {
+				native_currency_id,
+				stable_currency_id,
+				native_amount: pay_by_quoted,
+				stable_amount: contract_by,
+				price: quote_price,
+			},
+		);
+		Ok(SerpAdjustment {
+			currency_id: stable_currency_id,
+			direction: SerpDirection::Contract,
+			requested: contract_by,
+			executed: Zero::zero(),
+			native_amount: Zero::zero(),
+			price: quote_price,
+		})
+	}
+}
+
+impl<AccountId, BlockNumber, Market, AuctionModule, Store, Now, Duration, OnAdjustment>
+	AuctionHandler<AccountId, Market::Balance, BlockNumber, AuctionModule::AuctionId>
+	for SerpDownAuction<BlockNumber, Market, AuctionModule, Store, Now, Duration, OnAdjustment>
+where
+	AccountId: PartialEq,
+	Market: SerpMarket<AccountId> + Stp258CurrencyReservable<AccountId>,
+	AuctionModule: Auction<AccountId, BlockNumber, Balance = Market::Balance>,
+	Store: SerpDownAuctionStore<AuctionModule::AuctionId, Market::CurrencyId, Market::Balance>,
+	OnAdjustment: OnSerpAdjustment<Market::CurrencyId, Market::Balance>,
+{
+	fn on_new_bid(
+		_now: BlockNumber,
+		id: AuctionModule::AuctionId,
+		new_bid: (AccountId, Market::Balance),
+		last_bid: Option<(AccountId, Market::Balance)>,
+	) -> OnNewBidResult<BlockNumber> {
+		let reject = OnNewBidResult {
+			accept_bid: false,
+			auction_end_change: Change::NoChange,
+		};
+		let item = match Store::get(id) {
+			Some(item) => item,
+			None => return reject,
+		};
+		let (bidder, amount) = new_bid;
+		if amount < item.stable_amount {
+			return reject;
+		}
+
+		// the leading bidder always has `stable_amount` reserved
+		let stable_currency_id = item.stable_currency_id;
+		match last_bid {
+			Some((last_bidder, last_amount)) if last_bidder == bidder => {
+				if amount < last_amount {
+					return reject;
+				}
+			}
+			last_bid => {
+				if Market::reserve(stable_currency_id, &bidder, item.stable_amount).is_err() {
+					return reject;
+				}
+				if let Some((last_bidder, _)) = last_bid {
+					let _ = Market::unreserve(stable_currency_id, &last_bidder, item.stable_amount);
+				}
+			}
+		}
+
+		OnNewBidResult {
+			accept_bid: true,
+			auction_end_change: Change::NoChange,
+		}
+	}
+
+	fn on_auction_ended(id: AuctionModule::AuctionId, winner: Option<(AccountId, Market::Balance)>) {
+		let item = match Store::take(id) {
+			Some(item) => item,
+			None => return,
+		};
+		let (bidder, amount) = match winner {
+			Some(winner) => winner,
+			None => return,
+		};
+		let stable_currency_id = item.stable_currency_id;
+
+		// Only what is still reserved can be burned, for the same share of the
+		// native currency.
+		let burned = Market::reserved_balance(stable_currency_id, &bidder).min(item.stable_amount);
+		if burned.is_zero() {
+			return;
+		}
+		let native_amount = match share_of(item.native_amount, burned, amount) {
+			Some(native_amount) => native_amount,
+			None => {
+				let _ = Market::unreserve(stable_currency_id, &bidder, burned);
+				return;
+			}
+		};
+
+		// Pay out first, so the bid is returned if it cannot be paid.
+		if Market::deposit(item.native_currency_id, &bidder, native_amount).is_err() {
+			let _ = Market::unreserve(stable_currency_id, &bidder, burned);
+			return;
+		}
+		let remainder = Market::slash_reserved(stable_currency_id, &bidder, burned);
+
+		OnAdjustment::on_contract(&SerpAdjustment {
+			currency_id: stable_currency_id,
+			direction: SerpDirection::Contract,
+			requested: item.stable_amount,
+			executed: burned.saturating_sub(remainder),
+			native_amount,
+			price: item.price,
+		});
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{parameter_type_with_key, AuctionInfo};
+	use frame_support::parameter_types;
+	use std::{cell::RefCell, collections::BTreeMap};
+
+	const ALICE: u64 = 1;
+	const BOB: u64 = 2;
+	const CAROL: u64 = 3;
+	const SETT: u32 = 0;
+	const DNAR: u32 = 1;
+
+	parameter_type_with_key! {
+		pub ExistentialDeposits: |_currency_id: u32| -> u64 {
+			0
+		};
+	}
+
+	parameter_type_with_key! {
+		pub BaseUnits: |currency_id: u32| -> u64 {
+			match currency_id {
+				&SETT => 1_000,
+				_ => 100,
+			}
+		};
+	}
+
+	crate::create_mock_stp258_currency!(
+		Tokens,
+		u64,
+		u32,
+		u64,
+		i64,
+		u64,
+		ExistentialDeposits,
+		BaseUnits,
+		()
+	);
+
+	impl SerpMarket<u64> for Tokens {
+		fn expand_supply(
+			native_currency_id: u32,
+			stable_currency_id: u32,
+			expand_by: u64,
+			quote_price: u64,
+			pay_by_quoted: u64,
+			serpers: &u64,
+		) -> Result<SerpAdjustment<u32, u64>, DispatchError> {
+			Tokens::withdraw(native_currency_id, serpers, pay_by_quoted)?;
+			Tokens::deposit(stable_currency_id, serpers, expand_by)?;
+			Ok(SerpAdjustment {
+				currency_id: stable_currency_id,
+				direction: SerpDirection::Expand,
+				requested: expand_by,
+				executed: expand_by,
+				native_amount: pay_by_quoted,
+				price: quote_price,
+			})
+		}
+
+		fn contract_supply(
+			_: u32,
+			_: u32,
+			_: u64,
+			_: u64,
+			_: u64,
+			_: &u64,
+		) -> Result<SerpAdjustment<u32, u64>, DispatchError> {
+			Err(DispatchError::Other("Unused"))
+		}
+	}
+
+	thread_local! {
+		static AUCTIONS: RefCell<Vec<(u64, Option<u64>)>> = RefCell::new(Vec::new());
+		static ITEMS: RefCell<BTreeMap<u32, SerpDownAuctionItem<u32, u64>>> = RefCell::new(BTreeMap::new());
+		static ADJUSTMENTS: RefCell<Vec<SerpAdjustment<u32, u64>>> = RefCell::new(Vec::new());
+	}
+
+	pub struct MockAuction;
+	impl Auction<u64, u64> for MockAuction {
+		type AuctionId = u32;
+		type Balance = u64;
+
+		fn auction_info(id: u32) -> Option<AuctionInfo<u64, u64, u64>> {
+			AUCTIONS.with(|v| {
+				v.borrow().get(id as usize).map(|&(start, end)| AuctionInfo {
+					bid: None,
+					start,
+					end,
+				})
+			})
+		}
+
+		fn update_auction(_id: u32, _info: AuctionInfo<u64, u64, u64>) -> DispatchResult {
+			Ok(())
+		}
+
+		fn new_auction(start: u64, end: Option<u64>) -> Result<u32, DispatchError> {
+			AUCTIONS.with(|v| {
+				v.borrow_mut().push((start, end));
+				Ok(v.borrow().len() as u32 - 1)
+			})
+		}
+
+		fn remove_auction(_id: u32) {}
+	}
+
+	pub struct MockStore;
+	impl SerpDownAuctionStore<u32, u32, u64> for MockStore {
+		fn get(id: u32) -> Option<SerpDownAuctionItem<u32, u64>> {
+			ITEMS.with(|v| v.borrow().get(&id).copied())
+		}
+
+		fn insert(id: u32, item: SerpDownAuctionItem<u32, u64>) {
+			ITEMS.with(|v| v.borrow_mut().insert(id, item));
+		}
+
+		fn take(id: u32) -> Option<SerpDownAuctionItem<u32, u64>> {
+			ITEMS.with(|v| v.borrow_mut().remove(&id))
+		}
+	}
+
+	pub struct MockOnAdjustment;
+	impl OnSerpAdjustment<u32, u64> for MockOnAdjustment {
+		fn on_expand(adjustment: &SerpAdjustment<u32, u64>) {
+			ADJUSTMENTS.with(|v| v.borrow_mut().push(*adjustment));
+		}
+
+		fn on_contract(adjustment: &SerpAdjustment<u32, u64>) {
+			ADJUSTMENTS.with(|v| v.borrow_mut().push(*adjustment));
+		}
+	}
+
+	fn adjustments() -> Vec<SerpAdjustment<u32, u64>> {
+		ADJUSTMENTS.with(|v| v.borrow().clone())
+	}
+
+	parameter_types! {
+		pub const Now: u64 = 10;
+		pub const Duration: u64 = 20;
+	}
+
+	type SerpDown = SerpDownAuction<u64, Tokens, MockAuction, MockStore, Now, Duration, MockOnAdjustment>;
+
+	fn bid(id: u32, new_bid: (u64, u64), last_bid: Option<(u64, u64)>) -> bool {
+		SerpDown::on_new_bid(10, id, new_bid, last_bid).accept_bid
+	}
+
+	#[test]
+	fn contract_supply_should_open_auction() {
+		assert_eq!(
+			SerpDown::contract_supply(DNAR, SETT, 1_000, 200, 500, &CAROL),
+			Ok(SerpAdjustment {
+				currency_id: SETT,
+				direction: SerpDirection::Contract,
+				requested: 1_000,
+				executed: 0,
+				native_amount: 0,
+				price: 200,
+			})
+		);
+		assert_eq!(MockAuction::auction_info(0).map(|info| (info.start, info.end)), Some((10, Some(30))));
+		assert_eq!(
+			MockStore::get(0),
+			Some(SerpDownAuctionItem {
+				native_currency_id: DNAR,
+				stable_currency_id: SETT,
+				native_amount: 500,
+				stable_amount: 1_000,
+				price: 200,
+			})
+		);
+		assert_eq!(adjustments(), vec![]);
+	}
+
+	#[test]
+	fn expand_supply_should_forward_to_market() {
+		assert_eq!(Tokens::deposit(DNAR, &CAROL, 100), Ok(()));
+		assert_eq!(
+			SerpDown::expand_supply(DNAR, SETT, 100, 200, 50, &CAROL),
+			Ok(SerpAdjustment {
+				currency_id: SETT,
+				direction: SerpDirection::Expand,
+				requested: 100,
+				executed: 100,
+				native_amount: 50,
+				price: 200,
+			})
+		);
+		assert_eq!(SerpDown::free_balance(SETT, &CAROL), 100);
+		assert_eq!(SerpDown::free_balance(DNAR, &CAROL), 50);
+		assert_eq!(MockStore::get(0), None);
+		assert_eq!(
+			adjustments(),
+			vec![SerpAdjustment {
+				currency_id: SETT,
+				direction: SerpDirection::Expand,
+				requested: 100,
+				executed: 100,
+				native_amount: 50,
+				price: 200,
+			}]
+		);
+
+		assert!(SerpDown::expand_supply(DNAR, SETT, 100, 200, 100, &CAROL).is_err());
+		assert_eq!(adjustments().len(), 1);
+	}
+
+	#[test]
+	fn bids_should_be_reserved() {
+		assert_eq!(Tokens::deposit(SETT, &ALICE, 2_000), Ok(()));
+		assert_eq!(Tokens::deposit(SETT, &BOB, 2_000), Ok(()));
+		assert!(SerpDown::contract_supply(DNAR, SETT, 1_000, 200, 500, &CAROL).is_ok());
+
+		assert!(!bid(0, (ALICE, 999), None));
+		assert!(!bid(1, (ALICE, 1_000), None));
+		assert!(!bid(0, (CAROL, 1_000), None));
+
+		assert!(bid(0, (ALICE, 1_000), None));
+		assert_eq!(Tokens::reserved_balance(SETT, &ALICE), 1_000);
+
+		assert!(bid(0, (BOB, 1_200), Some((ALICE, 1_000))));
+		assert_eq!(Tokens::reserved_balance(SETT, &ALICE), 0);
+		assert_eq!(Tokens::reserved_balance(SETT, &BOB), 1_000);
+
+		assert!(!bid(0, (BOB, 1_100), Some((BOB, 1_200))));
+		assert!(bid(0, (BOB, 1_500), Some((BOB, 1_200))));
+		assert_eq!(Tokens::reserved_balance(SETT, &BOB), 1_000);
+		assert_eq!(Tokens::free_balance(SETT, &BOB), 1_000);
+
+		assert_eq!(Tokens::deposit(SETT, &CAROL, 500), Ok(()));
+		assert!(!bid(0, (CAROL, 3_000), Some((BOB, 1_500))));
+		assert_eq!(Tokens::reserved_balance(SETT, &BOB), 1_000);
+
+		assert!(bid(0, (ALICE, 2_500), Some((BOB, 1_500))));
+		assert_eq!(Tokens::reserved_balance(SETT, &BOB), 0);
+		assert_eq!(Tokens::reserved_balance(SETT, &ALICE), 1_000);
+	}
+
+	#[test]
+	fn auction_end_should_burn_bid_and_pay_native() {
+		assert_eq!(Tokens::deposit(SETT, &BOB, 2_000), Ok(()));
+		assert!(SerpDown::contract_supply(DNAR, SETT, 1_000, 200, 500, &CAROL).is_ok());
+		assert!(bid(0, (BOB, 1_500), None));
+
+		SerpDown::on_auction_ended(0, Some((BOB, 1_500)));
+		assert_eq!(Tokens::reserved_balance(SETT, &BOB), 0);
+		assert_eq!(Tokens::free_balance(SETT, &BOB), 1_000);
+		assert_eq!(Tokens::total_issuance(SETT), 1_000);
+		assert_eq!(Tokens::free_balance(DNAR, &BOB), 333);
+		assert_eq!(Tokens::total_issuance(DNAR), 333);
+		assert_eq!(MockStore::get(0), None);
+		assert_eq!(
+			adjustments(),
+			vec![SerpAdjustment {
+				currency_id: SETT,
+				direction: SerpDirection::Contract,
+				requested: 1_000,
+				executed: 1_000,
+				native_amount: 333,
+				price: 200,
+			}]
+		);
+
+		SerpDown::on_auction_ended(0, Some((BOB, 1_500)));
+		assert_eq!(Tokens::free_balance(SETT, &BOB), 1_000);
+		assert_eq!(Tokens::free_balance(DNAR, &BOB), 333);
+		assert_eq!(adjustments().len(), 1);
+	}
+
+	#[test]
+	fn auction_end_should_pay_whole_native_amount_to_lowest_bid() {
+		assert_eq!(Tokens::deposit(SETT, &BOB, 2_000), Ok(()));
+		assert!(SerpDown::contract_supply(DNAR, SETT, 1_000, 200, 500, &CAROL).is_ok());
+		assert!(bid(0, (BOB, 1_000), None));
+
+		SerpDown::on_auction_ended(0, Some((BOB, 1_000)));
+		assert_eq!(Tokens::free_balance(SETT, &BOB), 1_000);
+		assert_eq!(Tokens::free_balance(DNAR, &BOB), 500);
+		assert_eq!(adjustments().iter().map(|a| a.executed).collect::<Vec<_>>(), vec![1_000]);
+	}
+
+	#[test]
+	fn auction_end_should_refund_bid_if_native_cannot_be_paid() {
+		assert_eq!(Tokens::deposit(SETT, &BOB, 2_000), Ok(()));
+		assert_eq!(Tokens::deposit(DNAR, &ALICE, u64::max_value() - 100), Ok(()));
+		assert!(SerpDown::contract_supply(DNAR, SETT, 1_000, 200, 500, &CAROL).is_ok());
+		assert!(bid(0, (BOB, 1_500), None));
+
+		SerpDown::on_auction_ended(0, Some((BOB, 1_500)));
+		assert_eq!(Tokens::reserved_balance(SETT, &BOB), 0);
+		assert_eq!(Tokens::free_balance(SETT, &BOB), 2_000);
+		assert_eq!(Tokens::total_issuance(SETT), 2_000);
+		assert_eq!(Tokens::free_balance(DNAR, &BOB), 0);
+		assert_eq!(MockStore::get(0), None);
+		assert_eq!(adjustments(), vec![]);
+	}
+
+	#[test]
+	fn auction_end_should_scale_payout_to_reserved_bid() {
+		assert_eq!(Tokens::deposit(SETT, &BOB, 2_000), Ok(()));
+		assert!(SerpDown::contract_supply(DNAR, SETT, 1_000, 200, 500, &CAROL).is_ok());
+		assert!(bid(0, (BOB, 1_000), None));
+		assert_eq!(Tokens::slash_reserved(SETT, &BOB, 500), 0);
+
+		SerpDown::on_auction_ended(0, Some((BOB, 1_000)));
+		assert_eq!(Tokens::reserved_balance(SETT, &BOB), 0);
+		assert_eq!(Tokens::free_balance(SETT, &BOB), 1_000);
+		assert_eq!(Tokens::total_issuance(SETT), 1_000);
+		assert_eq!(Tokens::free_balance(DNAR, &BOB), 250);
+		assert_eq!(
+			adjustments(),
+			vec![SerpAdjustment {
+				currency_id: SETT,
+				direction: SerpDirection::Contract,
+				requested: 1_000,
+				executed: 500,
+				native_amount: 250,
+				price: 200,
+			}]
+		);
+	}
+
+	#[test]
+	fn auction_end_should_pay_nothing_if_bid_was_slashed() {
+		assert_eq!(Tokens::deposit(SETT, &BOB, 2_000), Ok(()));
+		assert!(SerpDown::contract_supply(DNAR, SETT, 1_000, 200, 500, &CAROL).is_ok());
+		assert!(bid(0, (BOB, 1_500), None));
+		assert_eq!(Tokens::slash_reserved(SETT, &BOB, 1_000), 0);
+
+		SerpDown::on_auction_ended(0, Some((BOB, 1_500)));
+		assert_eq!(Tokens::free_balance(DNAR, &BOB), 0);
+		assert_eq!(Tokens::total_issuance(DNAR), 0);
+		assert_eq!(adjustments(), vec![]);
+	}
+
+	#[test]
+	fn auction_without_bid_should_pay_nothing() {
+		assert!(SerpDown::contract_supply(DNAR, SETT, 1_000, 200, 500, &CAROL).is_ok());
+		SerpDown::on_auction_ended(0, None);
+		assert_eq!(Tokens::total_issuance(DNAR), 0);
+		assert_eq!(MockStore::get(0), None);
+		assert_eq!(adjustments(), vec![]);
+	}
+}
@@ -0,0 +1,345 @@
+use crate::arithmetic::SimpleArithmetic;
+use crate::serp_supply::{calculate_supply_change, mul_ratio, Rounding, SupplyChange, SupplyChangeError};
+use codec::{Decode, Encode};
+use frame_support::traits::Get;
+use sp_runtime::{traits::Saturating, FixedI128, FixedPointNumber, FixedU128, PerThing, Permill, RuntimeDebug};
+use sp_std::{
+	convert::{TryFrom, TryInto},
+	marker::PhantomData,
+};
+
+/// A policy deciding how much the SERP adjusts supply for a given deviation
+/// of `price` from `peg`.
+pub trait SerpElasticityPolicy<Balance> {
+	/// State carried between adjustments, kept in storage by the caller.
+	type State: Default;
+
+	/// Relative deviation from the peg within which supply is left alone.
+	fn tolerance() -> Permill;
+
+	/// Largest expansion per adjustment, as a share of total issuance.
+	fn max_expansion() -> Permill;
+
+	/// Largest contraction per adjustment, as a share of total issuance.
+	fn max_contraction() -> Permill {
+		Self::max_expansion()
+	}
+
+	/// The supply change for this adjustment.
+	fn supply_change(
+		price: FixedU128,
+		peg: FixedU128,
+		total_issuance: Balance,
+		state: &mut Self::State,
+	) -> Result<SupplyChange<Balance>, SupplyChangeError>;
+}
+
+/// `|price - peg| / peg` in `FixedU128` inner units, and whether price is
+/// above the peg.
+fn relative_deviation(price: FixedU128, peg: FixedU128) -> Result<(u128, bool), SupplyChangeError> {
+	let (price, peg) = (price.into_inner(), peg.into_inner());
+	if peg == 0 {
+		return Err(SupplyChangeError::ZeroPeg);
+	}
+	let (difference, above) = if price >= peg {
+		(price - peg, true)
+	} else {
+		(peg - price, false)
+	};
+	let deviation = mul_ratio(difference, FixedU128::DIV, peg, Rounding::Down).ok_or(SupplyChangeError::Overflow)?;
+	Ok((deviation, above))
+}
+
+fn within_tolerance(deviation: u128, tolerance: Permill) -> bool {
+	deviation <= u128::from(tolerance.deconstruct()) * (FixedU128::DIV / 1_000_000)
+}
+
+fn share_of<Balance: SimpleArithmetic + Copy>(
+	total_issuance: Balance,
+	share: Permill,
+) -> Result<Balance, SupplyChangeError> {
+	let issuance = TryInto::<u128>::try_into(total_issuance).map_err(|_| SupplyChangeError::Overflow)?;
+	let amount = mul_ratio(issuance, share.deconstruct().into(), 1_000_000, Rounding::Down)
+		.ok_or(SupplyChangeError::Overflow)?;
+	<Balance as TryFrom<u128>>::try_from(amount).map_err(|_| SupplyChangeError::Overflow)
+}
+
+/// Clamp `change` to the limits of the policy `P`.
+fn clamp<Balance, P>(
+	change: SupplyChange<Balance>,
+	total_issuance: Balance,
+) -> Result<SupplyChange<Balance>, SupplyChangeError>
+where
+	Balance: SimpleArithmetic + Copy,
+	P: SerpElasticityPolicy<Balance>,
+{
+	let (amount, expand) = match change {
+		SupplyChange::Expand(amount) => (amount.min(share_of(total_issuance, P::max_expansion())?), true),
+		SupplyChange::Contract(amount) => (amount.min(share_of(total_issuance, P::max_contraction())?), false),
+		SupplyChange::NoChange => return Ok(SupplyChange::NoChange),
+	};
+	Ok(if amount.is_zero() {
+		SupplyChange::NoChange
+	} else if expand {
+		SupplyChange::Expand(amount)
+	} else {
+		SupplyChange::Contract(amount)
+	})
+}
+
+/// Outside the tolerance band, adjust supply by a fixed `Step` of total
+/// issuance, whatever the size of the deviation.
+pub struct FixedBand<Tolerance, Step>(PhantomData<(Tolerance, Step)>);
+
+impl<Balance, Tolerance, Step> SerpElasticityPolicy<Balance> for FixedBand<Tolerance, Step>
+where
+	Balance: SimpleArithmetic + Copy,
+	Tolerance: Get<Permill>,
+	Step: Get<Permill>,
+{
+	type State = ();
+
+	fn tolerance() -> Permill {
+		Tolerance::get()
+	}
+
+	fn max_expansion() -> Permill {
+		Step::get()
+	}
+
+	fn supply_change(
+		price: FixedU128,
+		peg: FixedU128,
+		total_issuance: Balance,
+		_state: &mut (),
+	) -> Result<SupplyChange<Balance>, SupplyChangeError> {
+		let (deviation, above) = relative_deviation(price, peg)?;
+		if within_tolerance(deviation, Self::tolerance()) {
+			return Ok(SupplyChange::NoChange);
+		}
+		let step = share_of(total_issuance, Step::get())?;
+		let change = if above {
+			SupplyChange::Expand(step)
+		} else {
+			SupplyChange::Contract(step)
+		};
+		clamp::<Balance, Self>(change, total_issuance)
+	}
+}
+
+/// Outside the tolerance band, correct the full deviation as computed by
+/// `calculate_supply_change`, capped at `MaxExpansion` and `MaxContraction`.
+pub struct Proportional<Tolerance, MaxExpansion, MaxContraction>(
+	PhantomData<(Tolerance, MaxExpansion, MaxContraction)>,
+);
+
+impl<Balance, Tolerance, MaxExpansion, MaxContraction> SerpElasticityPolicy<Balance>
+	for Proportional<Tolerance, MaxExpansion, MaxContraction>
+where
+	Balance: SimpleArithmetic + Copy,
+	Tolerance: Get<Permill>,
+	MaxExpansion: Get<Permill>,
+	MaxContraction: Get<Permill>,
+{
+	type State = ();
+
+	fn tolerance() -> Permill {
+		Tolerance::get()
+	}
+
+	fn max_expansion() -> Permill {
+		MaxExpansion::get()
+	}
+
+	fn max_contraction() -> Permill {
+		MaxContraction::get()
+	}
+
+	fn supply_change(
+		price: FixedU128,
+		peg: FixedU128,
+		total_issuance: Balance,
+		_state: &mut (),
+	) -> Result<SupplyChange<Balance>, SupplyChangeError> {
+		let (deviation, _) = relative_deviation(price, peg)?;
+		if within_tolerance(deviation, Self::tolerance()) {
+			return Ok(SupplyChange::NoChange);
+		}
+		let change = calculate_supply_change(price, peg, total_issuance, Rounding::Down)?;
+		clamp::<Balance, Self>(change, total_issuance)
+	}
+}
+
+/// State of the `Pid` policy.
+#[derive(Encode, Decode, Clone, Copy, Default, Eq, PartialEq, RuntimeDebug)]
+pub struct PidState {
+	/// Sum of the relative deviations seen outside the tolerance band.
+	pub integral: FixedI128,
+	/// Relative deviation seen at the previous adjustment.
+	pub last_error: FixedI128,
+}
+
+/// A PID controller on the relative deviation `(price - peg) / peg`.
+///
+/// The output `Kp * error + Ki * integral + Kd * (error - last_error)` is the
+/// share of total issuance to expand (positive) or contract (negative) by,
+/// capped at `MaxExpansion` and `MaxContraction`. Inside the tolerance band
+/// no adjustment is made and the integral is left untouched.
+pub struct Pid<Tolerance, Kp, Ki, Kd, MaxExpansion, MaxContraction>(
+	PhantomData<(Tolerance, Kp, Ki, Kd, MaxExpansion, MaxContraction)>,
+);
+
+impl<Balance, Tolerance, Kp, Ki, Kd, MaxExpansion, MaxContraction> SerpElasticityPolicy<Balance>
+	for Pid<Tolerance, Kp, Ki, Kd, MaxExpansion, MaxContraction>
+where
+	Balance: SimpleArithmetic + Copy,
+	Tolerance: Get<Permill>,
+	Kp: Get<FixedI128>,
+	Ki: Get<FixedI128>,
+	Kd: Get<FixedI128>,
+	MaxExpansion: Get<Permill>,
+	MaxContraction: Get<Permill>,
+{
+	type State = PidState;
+
+	fn tolerance() -> Permill {
+		Tolerance::get()
+	}
+
+	fn max_expansion() -> Permill {
+		MaxExpansion::get()
+	}
+
+	fn max_contraction() -> Permill {
+		MaxContraction::get()
+	}
+
+	fn supply_change(
+		price: FixedU128,
+		peg: FixedU128,
+		total_issuance: Balance,
+		state: &mut PidState,
+	) -> Result<SupplyChange<Balance>, SupplyChangeError> {
+		let (deviation, above) = relative_deviation(price, peg)?;
+		let magnitude = i128::try_from(deviation).map_err(|_| SupplyChangeError::Overflow)?;
+		let error = FixedI128::from_inner(if above { magnitude } else { -magnitude });
+
+		let derivative = error.saturating_sub(state.last_error);
+		state.last_error = error;
+		if within_tolerance(deviation, Self::tolerance()) {
+			return Ok(SupplyChange::NoChange);
+		}
+		state.integral = state.integral.saturating_add(error);
+
+		let output = Kp::get()
+			.saturating_mul(error)
+			.saturating_add(Ki::get().saturating_mul(state.integral))
+			.saturating_add(Kd::get().saturating_mul(derivative))
+			.into_inner();
+		let share = output.checked_abs().map(|v| v as u128).unwrap_or(i128::max_value() as u128);
+
+		let issuance = TryInto::<u128>::try_into(total_issuance).map_err(|_| SupplyChangeError::Overflow)?;
+		let amount = mul_ratio(issuance, share, FixedI128::DIV as u128, Rounding::Down)
+			.and_then(|amount| <Balance as TryFrom<u128>>::try_from(amount).ok())
+			.unwrap_or_else(Balance::max_value);
+		let change = if output > 0 {
+			SupplyChange::Expand(amount)
+		} else {
+			SupplyChange::Contract(amount)
+		};
+		clamp::<Balance, Self>(change, total_issuance)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use frame_support::parameter_types;
+
+	parameter_types! {
+		pub const Tolerance: Permill = Permill::from_percent(2);
+		pub const Step: Permill = Permill::from_percent(1);
+		pub const MaxExpansion: Permill = Permill::from_percent(10);
+		pub const MaxContraction: Permill = Permill::from_percent(5);
+		pub const Kp: FixedI128 = FixedI128::from_inner(500_000_000_000_000_000);
+		pub const Ki: FixedI128 = FixedI128::from_inner(100_000_000_000_000_000);
+		pub const Kd: FixedI128 = FixedI128::from_inner(0);
+	}
+
+	type Band = FixedBand<Tolerance, Step>;
+	type Linear = Proportional<Tolerance, MaxExpansion, MaxContraction>;
+	type Controller = Pid<Tolerance, Kp, Ki, Kd, MaxExpansion, MaxContraction>;
+
+	fn price(percent: u128) -> FixedU128 {
+		FixedU128::saturating_from_rational(percent, 100)
+	}
+
+	#[test]
+	fn max_contraction_should_default_to_max_expansion() {
+		assert_eq!(<Band as SerpElasticityPolicy<u64>>::max_contraction(), Step::get());
+		assert_eq!(<Linear as SerpElasticityPolicy<u64>>::max_contraction(), MaxContraction::get());
+	}
+
+	#[test]
+	fn fixed_band_should_work() {
+		let peg = price(100);
+		assert_eq!(Band::supply_change(price(102), peg, 10_000u64, &mut ()), Ok(SupplyChange::NoChange));
+		assert_eq!(Band::supply_change(price(98), peg, 10_000u64, &mut ()), Ok(SupplyChange::NoChange));
+		assert_eq!(Band::supply_change(price(103), peg, 10_000u64, &mut ()), Ok(SupplyChange::Expand(100)));
+		assert_eq!(Band::supply_change(price(50), peg, 10_000u64, &mut ()), Ok(SupplyChange::Contract(100)));
+		assert_eq!(Band::supply_change(price(150), peg, 50u64, &mut ()), Ok(SupplyChange::NoChange));
+		assert_eq!(
+			Band::supply_change(price(150), FixedU128::from_inner(0), 10_000u64, &mut ()),
+			Err(SupplyChangeError::ZeroPeg)
+		);
+	}
+
+	#[test]
+	fn proportional_should_work() {
+		let peg = price(100);
+		assert_eq!(Linear::supply_change(price(101), peg, 10_000u64, &mut ()), Ok(SupplyChange::NoChange));
+		assert_eq!(Linear::supply_change(price(104), peg, 10_000u64, &mut ()), Ok(SupplyChange::Expand(400)));
+		assert_eq!(Linear::supply_change(price(97), peg, 10_000u64, &mut ()), Ok(SupplyChange::Contract(300)));
+		assert_eq!(Linear::supply_change(price(200), peg, 10_000u64, &mut ()), Ok(SupplyChange::Expand(1_000)));
+		assert_eq!(Linear::supply_change(price(10), peg, 10_000u64, &mut ()), Ok(SupplyChange::Contract(500)));
+	}
+
+	#[test]
+	fn pid_should_accumulate_integral() {
+		let peg = price(100);
+		let mut state = PidState::default();
+
+		// 0.5 * 0.04 + 0.1 * 0.04
+		assert_eq!(Controller::supply_change(price(104), peg, 10_000u64, &mut state), Ok(SupplyChange::Expand(240)));
+		assert_eq!(state.integral, FixedI128::saturating_from_rational(4, 100));
+		// 0.5 * 0.04 + 0.1 * 0.08
+		assert_eq!(Controller::supply_change(price(104), peg, 10_000u64, &mut state), Ok(SupplyChange::Expand(280)));
+
+		// inside the band the integral is kept
+		assert_eq!(Controller::supply_change(price(101), peg, 10_000u64, &mut state), Ok(SupplyChange::NoChange));
+		assert_eq!(state.integral, FixedI128::saturating_from_rational(8, 100));
+		assert_eq!(state.last_error, FixedI128::saturating_from_rational(1, 100));
+
+		// 0.5 * -0.2 + 0.1 * -0.12, capped at 5%
+		assert_eq!(Controller::supply_change(price(80), peg, 10_000u64, &mut state), Ok(SupplyChange::Contract(500)));
+	}
+
+	#[test]
+	fn pid_should_use_derivative() {
+		parameter_types! {
+			pub const NoGain: FixedI128 = FixedI128::from_inner(0);
+			pub const UnitGain: FixedI128 = FixedI128::from_inner(1_000_000_000_000_000_000);
+		}
+		type Derivative = Pid<Tolerance, NoGain, NoGain, UnitGain, MaxExpansion, MaxContraction>;
+
+		let peg = price(100);
+		let mut state = PidState::default();
+		assert_eq!(Derivative::supply_change(price(103), peg, 10_000u64, &mut state), Ok(SupplyChange::Expand(300)));
+		// error fell from 0.03 to -0.01 but is inside the band
+		assert_eq!(Derivative::supply_change(price(99), peg, 10_000u64, &mut state), Ok(SupplyChange::NoChange));
+		// error rose from -0.01 to 0.05
+		assert_eq!(Derivative::supply_change(price(105), peg, 10_000u64, &mut state), Ok(SupplyChange::Expand(600)));
+		// no change in error
+		assert_eq!(Derivative::supply_change(price(105), peg, 10_000u64, &mut state), Ok(SupplyChange::NoChange));
+	}
+}
@@ -0,0 +1,155 @@
+use crate::GetByKey;
+use codec::{Decode, Encode};
+use sp_runtime::{traits::AtLeast32BitUnsigned, RuntimeDebug};
+use sp_std::marker::PhantomData;
+
+/// The frequency of adjustments for the Currency supply.
+#[derive(Encode, Decode, Clone, Copy, Default, Eq, PartialEq, RuntimeDebug)]
+pub struct ElastAdjustmentFrequency<BlockNumber> {
+	/// Number of blocks for adjustment frequency. Zero disables adjustments.
+	pub adjustment_frequency: BlockNumber,
+	/// Adjustments happen on blocks where
+	/// `now % adjustment_frequency == offset % adjustment_frequency`.
+	pub offset: BlockNumber,
+}
+
+impl<BlockNumber: AtLeast32BitUnsigned + Copy> ElastAdjustmentFrequency<BlockNumber> {
+	/// Adjust every `adjustment_frequency` blocks, starting at block zero.
+	pub fn new(adjustment_frequency: BlockNumber) -> Self {
+		Self::with_offset(adjustment_frequency, BlockNumber::zero())
+	}
+
+	/// Adjust every `adjustment_frequency` blocks, `offset` blocks after
+	/// each multiple of the frequency.
+	pub fn with_offset(adjustment_frequency: BlockNumber, offset: BlockNumber) -> Self {
+		Self {
+			adjustment_frequency,
+			offset,
+		}
+	}
+
+	/// Adjust every `adjustment_frequency` blocks, offset by the position of
+	/// the currency so that currencies sharing a frequency adjust on
+	/// different blocks.
+	pub fn staggered(adjustment_frequency: BlockNumber, index: u32) -> Self {
+		Self::with_offset(adjustment_frequency, BlockNumber::from(index))
+	}
+
+	/// Whether an adjustment is due at block `now`.
+	pub fn is_due(&self, now: BlockNumber) -> bool {
+		let frequency = self.adjustment_frequency;
+		!frequency.is_zero() && now % frequency == self.offset % frequency
+	}
+
+	/// The first block strictly after `now` at which an adjustment is due, or
+	/// `None` if adjustments are disabled or the block number overflows.
+	pub fn next_adjustment(&self, now: BlockNumber) -> Option<BlockNumber> {
+		let frequency = self.adjustment_frequency;
+		if frequency.is_zero() {
+			return None;
+		}
+		let position = now % frequency;
+		let offset = self.offset % frequency;
+		let delta = if offset > position {
+			offset - position
+		} else {
+			(frequency - position).checked_add(&offset)?
+		};
+		now.checked_add(&delta)
+	}
+}
+
+/// A schedule of SERP adjustments per currency.
+pub trait SerpSchedule<CurrencyId, BlockNumber> {
+	/// Whether the supply of `currency_id` should be adjusted at block `now`.
+	fn is_due(currency_id: CurrencyId, now: BlockNumber) -> bool;
+
+	/// The first block strictly after `now` at which the supply of
+	/// `currency_id` should be adjusted.
+	fn next_adjustment(currency_id: CurrencyId, now: BlockNumber) -> Option<BlockNumber>;
+}
+
+/// A `SerpSchedule` reading the `ElastAdjustmentFrequency` of every currency
+/// from `Frequencies`, typically declared with `parameter_type_with_key!`.
+pub struct ElastAdjustmentSchedule<Frequencies>(PhantomData<Frequencies>);
+
+impl<CurrencyId, BlockNumber, Frequencies> SerpSchedule<CurrencyId, BlockNumber> for ElastAdjustmentSchedule<Frequencies>
+where
+	BlockNumber: AtLeast32BitUnsigned + Copy,
+	Frequencies: GetByKey<CurrencyId, ElastAdjustmentFrequency<BlockNumber>>,
+{
+	fn is_due(currency_id: CurrencyId, now: BlockNumber) -> bool {
+		Frequencies::get(&currency_id).is_due(now)
+	}
+
+	fn next_adjustment(currency_id: CurrencyId, now: BlockNumber) -> Option<BlockNumber> {
+		Frequencies::get(&currency_id).next_adjustment(now)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::parameter_type_with_key;
+
+	const USDJ: u32 = 0;
+	const EURJ: u32 = 1;
+	const GBPJ: u32 = 2;
+
+	parameter_type_with_key! {
+		pub Frequencies: |currency_id: u32| -> ElastAdjustmentFrequency<u64> {
+			match currency_id {
+				&USDJ | &EURJ => ElastAdjustmentFrequency::staggered(10, *currency_id),
+				_ => ElastAdjustmentFrequency::new(0),
+			}
+		};
+	}
+
+	type Schedule = ElastAdjustmentSchedule<Frequencies>;
+
+	#[test]
+	fn is_due_should_work() {
+		let frequency = ElastAdjustmentFrequency::with_offset(5u64, 7);
+		let due: Vec<u64> = (0..20).filter(|now| frequency.is_due(*now)).collect();
+		assert_eq!(due, vec![2, 7, 12, 17]);
+
+		assert!(ElastAdjustmentFrequency::new(1u64).is_due(3));
+		assert!(!ElastAdjustmentFrequency::new(0u64).is_due(0));
+	}
+
+	#[test]
+	fn next_adjustment_should_work() {
+		let frequency = ElastAdjustmentFrequency::with_offset(5u64, 2);
+		assert_eq!(frequency.next_adjustment(0), Some(2));
+		assert_eq!(frequency.next_adjustment(2), Some(7));
+		assert_eq!(frequency.next_adjustment(3), Some(7));
+		assert_eq!(frequency.next_adjustment(6), Some(7));
+		assert_eq!(ElastAdjustmentFrequency::new(1u64).next_adjustment(3), Some(4));
+		assert_eq!(ElastAdjustmentFrequency::new(0u64).next_adjustment(3), None);
+		assert_eq!(ElastAdjustmentFrequency::new(2u64).next_adjustment(u64::max_value() - 1), None);
+	}
+
+	#[test]
+	fn next_adjustment_should_be_due() {
+		for offset in 0..7u64 {
+			let frequency = ElastAdjustmentFrequency::with_offset(7u64, offset);
+			for now in 0..30 {
+				let next = frequency.next_adjustment(now).unwrap();
+				assert!(next > now && next <= now + 7);
+				assert!(frequency.is_due(next));
+				assert!((now + 1..next).all(|block| !frequency.is_due(block)));
+			}
+		}
+	}
+
+	#[test]
+	fn schedule_should_stagger_currencies() {
+		assert!(Schedule::is_due(USDJ, 20));
+		assert!(!Schedule::is_due(EURJ, 20));
+		assert!(Schedule::is_due(EURJ, 21));
+		assert!(!Schedule::is_due(GBPJ, 20));
+		assert_eq!(Schedule::next_adjustment(USDJ, 20), Some(30));
+		assert_eq!(Schedule::next_adjustment(EURJ, 20), Some(21));
+		assert_eq!(Schedule::next_adjustment(GBPJ, 20), None);
+	}
+}
@@ -0,0 +1,263 @@
+use crate::arithmetic::SimpleArithmetic;
+use codec::{Decode, Encode};
+use sp_core::U256;
+use sp_runtime::{DispatchError, FixedPointNumber, FixedU128, RuntimeDebug};
+use sp_std::convert::{TryFrom, TryInto};
+
+/// Rounding applied to the supply change when it is not a whole amount.
+#[derive(Encode, Decode, Clone, Copy, Eq, PartialEq, RuntimeDebug)]
+pub enum Rounding {
+	/// Round towards zero.
+	Down,
+	/// Round away from zero.
+	Up,
+	/// Round to the nearest amount, halves away from zero.
+	Nearest,
+}
+
+/// The supply adjustment needed to bring a currency back to its peg.
+#[derive(Encode, Decode, Clone, Copy, Eq, PartialEq, RuntimeDebug)]
+pub enum SupplyChange<Balance> {
+	/// Price is above the peg, issue this amount.
+	Expand(Balance),
+	/// Price is below the peg, burn this amount.
+	Contract(Balance),
+	/// Price is on the peg, or the change rounds to zero.
+	NoChange,
+}
+
+/// The reason a supply change cannot be calculated.
+#[derive(Encode, Decode, Clone, Copy, Eq, PartialEq, RuntimeDebug)]
+pub enum SupplyChangeError {
+	/// The peg price is zero.
+	ZeroPeg,
+	/// The total issuance does not fit in `u128`, or the supply change does
+	/// not fit in the balance type.
+	Overflow,
+}
+
+impl From<SupplyChangeError> for DispatchError {
+	fn from(e: SupplyChangeError) -> Self {
+		match e {
+			SupplyChangeError::ZeroPeg => DispatchError::Other("ZeroPeg"),
+			SupplyChangeError::Overflow => DispatchError::Other("SupplyChangeOverflow"),
+		}
+	}
+}
+
+/// Calculate the supply change that moves `price` back to `peg`, assuming
+/// price scales inversely with `total_issuance`.
+///
+/// The change is `total_issuance * |price - peg| / peg`, computed exactly and
+/// rounded once according to `rounding`. A contraction never exceeds
+/// `total_issuance`.
+pub fn calculate_supply_change<Balance: SimpleArithmetic + Copy>(
+	price: FixedU128,
+	peg: FixedU128,
+	total_issuance: Balance,
+	rounding: Rounding,
+) -> Result<SupplyChange<Balance>, SupplyChangeError> {
+	let peg = peg.into_inner();
+	if peg == 0 {
+		return Err(SupplyChangeError::ZeroPeg);
+	}
+	let price = price.into_inner();
+	let issuance: u128 = TryInto::<u128>::try_into(total_issuance).map_err(|_| SupplyChangeError::Overflow)?;
+
+	let (deviation, expand) = if price >= peg {
+		(price - peg, true)
+	} else {
+		(peg - price, false)
+	};
+	let amount = mul_ratio(issuance, deviation, peg, rounding).ok_or(SupplyChangeError::Overflow)?;
+	if amount == 0 {
+		return Ok(SupplyChange::NoChange);
+	}
+	let amount = <Balance as TryFrom<u128>>::try_from(amount).map_err(|_| SupplyChangeError::Overflow)?;
+
+	if expand {
+		Ok(SupplyChange::Expand(amount))
+	} else {
+		Ok(SupplyChange::Contract(amount))
+	}
+}
+
+/// `a * numerator / denominator` rounded according to `rounding`, or `None`
+/// if `denominator` is zero or the result does not fit in `u128`.
+pub(crate) fn mul_ratio(a: u128, numerator: u128, denominator: u128, rounding: Rounding) -> Option<u128> {
+	if denominator == 0 {
+		return None;
+	}
+	let product = U256::from(a) * U256::from(numerator);
+	let denominator = U256::from(denominator);
+	let mut quotient = product / denominator;
+	let remainder = product % denominator;
+
+	let round_up = match rounding {
+		Rounding::Down => false,
+		Rounding::Up => !remainder.is_zero(),
+		Rounding::Nearest => remainder * U256::from(2u8) >= denominator,
+	};
+	if round_up {
+		quotient = quotient + U256::from(1u8);
+	}
+
+	if quotient > U256::from(u128::max_value()) {
+		None
+	} else {
+		Some(quotient.low_u128())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn rational(n: u128, d: u128) -> FixedU128 {
+		FixedU128::saturating_from_rational(n, d)
+	}
+
+	#[test]
+	fn supply_change_should_follow_deviation() {
+		let one = FixedU128::saturating_from_integer(1);
+		assert_eq!(
+			calculate_supply_change(rational(11, 10), one, 1_000u64, Rounding::Down),
+			Ok(SupplyChange::Expand(100))
+		);
+		assert_eq!(
+			calculate_supply_change(rational(9, 10), one, 1_000u64, Rounding::Down),
+			Ok(SupplyChange::Contract(100))
+		);
+		assert_eq!(
+			calculate_supply_change(one, one, 1_000u64, Rounding::Up),
+			Ok(SupplyChange::NoChange)
+		);
+		assert_eq!(
+			calculate_supply_change(rational(3, 1), rational(2, 1), 1_000u64, Rounding::Down),
+			Ok(SupplyChange::Expand(500))
+		);
+		assert_eq!(
+			calculate_supply_change(FixedU128::from_inner(0), rational(2, 1), 1_000u64, Rounding::Up),
+			Ok(SupplyChange::Contract(1_000))
+		);
+	}
+
+	#[test]
+	fn supply_change_should_round() {
+		let one = FixedU128::saturating_from_integer(1);
+		let price = rational(1_005, 1_000);
+		assert_eq!(
+			calculate_supply_change(price, one, 100u64, Rounding::Down),
+			Ok(SupplyChange::NoChange)
+		);
+		assert_eq!(
+			calculate_supply_change(price, one, 100u64, Rounding::Nearest),
+			Ok(SupplyChange::Expand(1))
+		);
+		assert_eq!(
+			calculate_supply_change(price, one, 100u64, Rounding::Up),
+			Ok(SupplyChange::Expand(1))
+		);
+		assert_eq!(
+			calculate_supply_change(rational(1_004, 1_000), one, 100u64, Rounding::Nearest),
+			Ok(SupplyChange::NoChange)
+		);
+	}
+
+	#[test]
+	fn supply_change_should_fail_on_zero_peg() {
+		assert_eq!(
+			calculate_supply_change(
+				FixedU128::saturating_from_integer(1),
+				FixedU128::from_inner(0),
+				1_000u64,
+				Rounding::Down
+			),
+			Err(SupplyChangeError::ZeroPeg)
+		);
+	}
+
+	#[test]
+	fn supply_change_should_fail_on_overflow() {
+		assert_eq!(
+			calculate_supply_change(
+				rational(3, 1),
+				FixedU128::saturating_from_integer(1),
+				u64::max_value(),
+				Rounding::Down
+			),
+			Err(SupplyChangeError::Overflow)
+		);
+		assert_eq!(
+			calculate_supply_change(
+				FixedU128::from_inner(u128::max_value()),
+				rational(1, 1_000_000),
+				u128::max_value(),
+				Rounding::Down
+			),
+			Err(SupplyChangeError::Overflow)
+		);
+	}
+
+	#[test]
+	fn mul_ratio_should_work() {
+		assert_eq!(mul_ratio(u128::max_value(), 3, 3, Rounding::Down), Some(u128::max_value()));
+		assert_eq!(mul_ratio(u128::max_value(), 2, 1, Rounding::Down), None);
+		assert_eq!(mul_ratio(7, 1, 2, Rounding::Down), Some(3));
+		assert_eq!(mul_ratio(7, 1, 2, Rounding::Up), Some(4));
+		assert_eq!(mul_ratio(7, 1, 2, Rounding::Nearest), Some(4));
+		assert_eq!(mul_ratio(7, 1, 3, Rounding::Nearest), Some(2));
+		assert_eq!(mul_ratio(7, 1, 0, Rounding::Down), None);
+	}
+
+	#[test]
+	fn contraction_should_never_exceed_total_issuance() {
+		let mut seed = 42u64;
+		let mut next = || {
+			seed = seed.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1_442_695_040_888_963_407);
+			seed
+		};
+
+		for _ in 0..10_000 {
+			let peg = FixedU128::from_inner(u128::from(next()) + 1);
+			let price = FixedU128::from_inner(u128::from(next()) % peg.into_inner());
+			let issuance = next();
+
+			for rounding in &[Rounding::Down, Rounding::Up, Rounding::Nearest] {
+				match calculate_supply_change(price, peg, issuance, *rounding) {
+					Ok(SupplyChange::Contract(amount)) => assert!(amount <= issuance),
+					Ok(SupplyChange::NoChange) => {}
+					other => panic!("unexpected {:?}", other),
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn rounding_modes_should_be_ordered() {
+		let mut seed = 7u64;
+		let mut next = || {
+			seed = seed.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1_442_695_040_888_963_407);
+			seed
+		};
+		let amount = |change: SupplyChange<u64>| match change {
+			SupplyChange::Expand(a) | SupplyChange::Contract(a) => a,
+			SupplyChange::NoChange => 0,
+		};
+
+		for _ in 0..10_000 {
+			let peg = FixedU128::from_inner(u128::from(next()) + 1);
+			let price = FixedU128::from_inner(u128::from(next()));
+			let issuance = next() >> 8;
+
+			let down = calculate_supply_change(price, peg, issuance, Rounding::Down);
+			let nearest = calculate_supply_change(price, peg, issuance, Rounding::Nearest);
+			let up = calculate_supply_change(price, peg, issuance, Rounding::Up);
+			if let (Ok(down), Ok(nearest), Ok(up)) = (down, nearest, up) {
+				assert!(amount(down) <= amount(nearest));
+				assert!(amount(nearest) <= amount(up));
+				assert!(amount(up) - amount(down) <= 1);
+			}
+		}
+	}
+}
@@ -4,14 +4,15 @@
 //! Traits for FRAME.
 //!
 //! NOTE: If you're looking for `parameter_types`, it has moved in to the top-level module.
-use sp_std::{prelude::*, result, fmt::Debug};
-use codec::{FullCodec, Encode, Decode};
+use crate::stp258::BalanceStatus;
+use sp_std::{result, fmt::Debug};
+use codec::FullCodec;
 use sp_runtime::{
-	RuntimeDebug,DispatchResult, DispatchError,
+	DispatchResult, DispatchError,
 	traits::{MaybeSerializeDeserialize, AtLeast32BitUnsigned}
 };
-use frame_support::{
-	traits::*,
+use frame_support::traits::{
+	ExistenceRequirement, Get, Imbalance, LockIdentifier, SignedImbalance, WithdrawReasons,
 };
 
 /// Re-expected for the macro.
@@ -68,7 +69,7 @@ pub trait Currency<AccountId> {
 	/// This is just the same as burning and issuing the same amount and has no effect on the
 	/// total issuance.
 	fn pair(amount: Self::Balance) -> (Self::PositiveImbalance, Self::NegativeImbalance) {
-		(Self::burn(amount.clone()), Self::issue(amount))
+		(Self::burn(amount), Self::issue(amount))
 	}
 
 	/// The 'free' balance of a given account.
@@ -135,7 +136,10 @@ pub trait Currency<AccountId> {
 	) -> result::Result<(), Self::NegativeImbalance> {
 		let v = value.peek();
 		match Self::deposit_into_existing(who, v) {
-			Ok(opposite) => Ok(drop(value.offset(opposite))),
+			Ok(opposite) => {
+				let _ = value.offset(opposite);
+				Ok(())
+			}
 			_ => Err(value),
 		}
 	}
@@ -155,7 +159,7 @@ pub trait Currency<AccountId> {
 		value: Self::NegativeImbalance,
 	) {
 		let v = value.peek();
-		drop(value.offset(Self::deposit_creating(who, v)));
+		let _ = value.offset(Self::deposit_creating(who, v));
 	}
 
 	/// Removes some free balance from `who` account for `reason` if possible. If `liveness` is
@@ -182,7 +186,10 @@ pub trait Currency<AccountId> {
 	) -> result::Result<(), Self::PositiveImbalance> {
 		let v = value.peek();
 		match Self::withdraw(who, v, reasons, liveness) {
-			Ok(opposite) => Ok(drop(value.offset(opposite))),
+			Ok(opposite) => {
+				let _ = value.offset(opposite);
+				Ok(())
+			}
 			_ => Err(value),
 		}
 	}
@@ -198,15 +205,6 @@ pub trait Currency<AccountId> {
 	) -> SignedImbalance<Self::Balance, Self::PositiveImbalance>;
 }
 
-/// Status of funds.
-#[derive(PartialEq, Eq, Clone, Copy, Encode, Decode, RuntimeDebug)]
-pub enum BalanceStatus {
-	/// Funds are free, as corresponding to `free` item in Balances.
-	Free,
-	/// Funds are reserved, as corresponding to `reserved` item in Balances.
-	Reserved,
-}
-
 /// A currency where funds can be reserved from the user.
 pub trait ReservableCurrency<AccountId>: Currency<AccountId> {
 	/// Same result as `reserve(who, value)` (but without the side-effects) assuming there
@@ -5,9 +5,10 @@ pub use frame_support::{
 	codec::{Encode, Decode, EncodeLike},
 	Parameter
 };
+use frame_support::traits::Imbalance;
 use sp_runtime::{
 	traits::{AtLeast32BitUnsigned, MaybeSerializeDeserialize},
-	DispatchError, DispatchResult, 
+	DispatchError, DispatchResult, RuntimeDebug,
 };
 use sp_std::{
 	cmp::{Eq, PartialEq},
@@ -191,6 +192,121 @@ pub trait Stp258CurrencyReservable<AccountId>: Stp258Currency<AccountId> {
 	) -> result::Result<Self::Balance, DispatchError>;
 }
 
+/// A fungible multi-stable-currency system whose supply can be changed
+/// through imbalances.
+///
+/// This allows SERP expansion to be expressed as `issue` followed by
+/// `resolve_creating`, and contraction as `burn` followed by `settle`, so the
+/// total issuance and the balances always change together. Imbalances are
+/// tied to the `currency_id` they were created with and must only be resolved
+/// against the same currency.
+pub trait Stp258CurrencyImbalanced<AccountId>: Stp258Currency<AccountId> {
+	/// The opaque token type for an imbalance. This is returned by unbalanced
+	/// operations and must be dealt with. It may be dropped but cannot be
+	/// cloned.
+	type PositiveImbalance: Imbalance<Self::Balance, Opposite = Self::NegativeImbalance>;
+
+	/// The opaque token type for an imbalance. This is returned by unbalanced
+	/// operations and must be dealt with. It may be dropped but cannot be
+	/// cloned.
+	type NegativeImbalance: Imbalance<Self::Balance, Opposite = Self::PositiveImbalance>;
+
+	/// Reduce the total issuance of `currency_id` by `amount` and return the
+	/// according imbalance. The imbalance will typically be used to reduce an
+	/// account by the same amount with e.g. `settle`.
+	///
+	/// This is infallible, but doesn't guarantee that the entire `amount` is
+	/// burnt, for example in the case of underflow.
+	fn burn(currency_id: Self::CurrencyId, amount: Self::Balance) -> Self::PositiveImbalance;
+
+	/// Increase the total issuance of `currency_id` by `amount` and return the
+	/// according imbalance. The imbalance will typically be used to increase
+	/// an account by the same amount with e.g. `resolve_creating`.
+	///
+	/// This is infallible, but doesn't guarantee that the entire `amount` is
+	/// issued, for example in the case of overflow.
+	fn issue(currency_id: Self::CurrencyId, amount: Self::Balance) -> Self::NegativeImbalance;
+
+	/// Produce a pair of imbalances that cancel each other out exactly.
+	///
+	/// This is just the same as burning and issuing the same amount and has
+	/// no effect on the total issuance.
+	fn pair(
+		currency_id: Self::CurrencyId,
+		amount: Self::Balance,
+	) -> (Self::PositiveImbalance, Self::NegativeImbalance) {
+		(Self::burn(currency_id, amount), Self::issue(currency_id, amount))
+	}
+
+	/// Mints `value` to the free balance of `who` under `currency_id`.
+	///
+	/// If `who` doesn't exist, nothing is done and an `Err` returned.
+	fn deposit_into_existing(
+		currency_id: Self::CurrencyId,
+		who: &AccountId,
+		value: Self::Balance,
+	) -> result::Result<Self::PositiveImbalance, DispatchError>;
+
+	/// Adds up to `value` to the free balance of `who` under `currency_id`. If
+	/// `who` doesn't exist, it is created.
+	///
+	/// Infallible.
+	fn deposit_creating(currency_id: Self::CurrencyId, who: &AccountId, value: Self::Balance)
+		-> Self::PositiveImbalance;
+
+	/// Similar to `deposit_into_existing`, only accepts a `NegativeImbalance`
+	/// and returns nothing on success.
+	fn resolve_into_existing(
+		currency_id: Self::CurrencyId,
+		who: &AccountId,
+		value: Self::NegativeImbalance,
+	) -> result::Result<(), Self::NegativeImbalance> {
+		let v = value.peek();
+		match Self::deposit_into_existing(currency_id, who, v) {
+			Ok(opposite) => {
+				let _ = value.offset(opposite);
+				Ok(())
+			}
+			_ => Err(value),
+		}
+	}
+
+	/// Similar to `deposit_creating`, only accepts a `NegativeImbalance` and
+	/// returns nothing on success.
+	fn resolve_creating(currency_id: Self::CurrencyId, who: &AccountId, value: Self::NegativeImbalance) {
+		let v = value.peek();
+		let _ = value.offset(Self::deposit_creating(currency_id, who, v));
+	}
+
+	/// Removes `value` from the free balance of `who` under `currency_id`,
+	/// respecting locks, and returns a `NegativeImbalance` of `value`.
+	///
+	/// Named apart from `Stp258Currency::withdraw`, which changes the total
+	/// issuance directly.
+	fn withdraw_imbalance(
+		currency_id: Self::CurrencyId,
+		who: &AccountId,
+		value: Self::Balance,
+	) -> result::Result<Self::NegativeImbalance, DispatchError>;
+
+	/// Similar to `withdraw_imbalance`, only accepts a `PositiveImbalance` and
+	/// returns nothing on success.
+	fn settle(
+		currency_id: Self::CurrencyId,
+		who: &AccountId,
+		value: Self::PositiveImbalance,
+	) -> result::Result<(), Self::PositiveImbalance> {
+		let v = value.peek();
+		match Self::withdraw_imbalance(currency_id, who, v) {
+			Ok(opposite) => {
+				let _ = value.offset(opposite);
+				Ok(())
+			}
+			_ => Err(value),
+		}
+	}
+}
+
 /// Abstraction over a fungible (single) currency system.
 pub trait Stp258Asset<AccountId> {
 	/// The balance of an account.
@@ -350,6 +466,34 @@ impl<AccountId, CurrencyId, Balance> OnDust<AccountId, CurrencyId, Balance> for
 	fn on_dust(_: &AccountId, _: CurrencyId, _: Balance) {}
 }
 
+/// Direction of a SERP supply adjustment.
+#[derive(Encode, Decode, Clone, Copy, Eq, PartialEq, RuntimeDebug)]
+pub enum SerpDirection {
+	/// Stable currency supply was expanded.
+	Expand,
+	/// Stable currency supply was contracted.
+	Contract,
+}
+
+/// The outcome of a SERP supply adjustment.
+#[derive(Encode, Decode, Clone, Copy, Eq, PartialEq, RuntimeDebug)]
+pub struct SerpAdjustment<CurrencyId, Balance> {
+	/// The stable currency adjusted.
+	pub currency_id: CurrencyId,
+	/// Whether supply was expanded or contracted.
+	pub direction: SerpDirection,
+	/// Amount of stable currency the SERP asked to mint or burn.
+	pub requested: Balance,
+	/// Amount of stable currency actually minted or burned, which may be
+	/// less than `requested` if the market clamps or partially fills.
+	pub executed: Balance,
+	/// Amount of native currency paid by (on expansion) or to (on
+	/// contraction) the serpers.
+	pub native_amount: Balance,
+	/// The quote price of the native currency used.
+	pub price: Balance,
+}
+
 /// Abstraction over a `serp_market` system for the Setheum Elastic Reserve Protocol (SERP) Market for `Stp258Currency` .
 pub trait SerpMarket<AccountId>: Stp258Currency<AccountId> {
 	/// Called when `expand_supply` is received from the SERP.
@@ -357,28 +501,32 @@ pub trait SerpMarket<AccountId>: Stp258Currency<AccountId> {
 	/// then `amount` will be slashed from `serpup_from` and update
 	/// `new_supply`. `quote_price` is the price ( relative to the settcurrency) of 
 	/// the `native_currency` used to expand settcurrency supply.
+	/// `pay_by_quoted` is the amount of `native_currency` the `serpers` pay
+	/// for the expanded supply at `quote_price`.
 	fn expand_supply(
 		native_currency_id: Self::CurrencyId, 
 		stable_currency_id: Self::CurrencyId, 
 		expand_by: Self::Balance, 
 		quote_price: Self::Balance, 
-		// pay_by_quoted: Self::Balance, 
-		// serpers: &AccountId
-	) -> DispatchResult;
+		pay_by_quoted: Self::Balance, 
+		serpers: &AccountId,
+	) -> result::Result<SerpAdjustment<Self::CurrencyId, Self::Balance>, DispatchError>;
 
 	/// Called when `contract_supply` is received from the SERP.
 	/// Implementation should `deposit` the `base_currency_id` (The Native Currency) 
 	/// of `amount` to `serpup_to`, then `amount` will be slashed from `serpup_from` 
 	/// and update `new_supply`. `quote_price` is the price ( relative to the settcurrency) of 
 	/// the `native_currency` used to contract settcurrency supply.
+	/// `pay_by_quoted` is the amount of `native_currency` paid to the
+	/// `serpers` for the contracted supply at `quote_price`.
 	fn contract_supply(
 		native_currency_id: Self::CurrencyId, 
 		stable_currency_id: Self::CurrencyId, 
 		contract_by: Self::Balance, 
 		quote_price: Self::Balance, 
-		// pay_by_quoted: Self::Balance, 
-		// serpers: &AccountId
-	) -> DispatchResult;
+		pay_by_quoted: Self::Balance, 
+		serpers: &AccountId,
+	) -> result::Result<SerpAdjustment<Self::CurrencyId, Self::Balance>, DispatchError>;
 }
 
 /// Abstraction over a fungible multi-stable-currency Token Elasticity of Supply system.
@@ -386,23 +534,26 @@ pub trait SerpTes<AccountId>: Stp258Currency<AccountId> {
 	type BlockNumber: Decode + Encode + EncodeLike + Clone + Default;
 	/// Contracts or expands the currency supply based on conditions.
 	/// Filters through the conditions to see whether it's time to adjust supply or not.
+	/// Returns the adjustment made, if any.
 	fn on_serp_block(
 		now: Self::BlockNumber, 
 		stable_currency_id: Self::CurrencyId,
 		stable_currency_price: Self::Balance,
 		native_currency_id: Self::CurrencyId,
 		native_currency_price: Self::Balance, 
-	) -> DispatchResult;
+	) -> result::Result<Option<SerpAdjustment<Self::CurrencyId, Self::Balance>>, DispatchError>;
 
 	/// Calculate the amount of supply change from a fraction given as `numerator` and `denominator`.
 	fn supply_change(currency_id: Self::CurrencyId, new_price: Self::Balance) -> Self::Balance;	
 
+	/// Expands or contracts the supply of `stable_currency_id`, returning the
+	/// adjustment made, or `None` if the price needs no adjustment.
 	fn serp_elast(
 		stable_currency_id: Self::CurrencyId, 
 		stable_currency_price: Self::Balance, 
 		native_currency_id: Self::CurrencyId,
 		native_currency_price: Self::Balance,
-	) -> DispatchResult;
+	) -> result::Result<Option<SerpAdjustment<Self::CurrencyId, Self::Balance>>, DispatchError>;
 }
 
 /// Expected price oracle interface. `fetch_price` must return the amount of Coins exchanged for the tracked value.
@@ -414,7 +565,13 @@ pub trait FetchPrice<Balance> {
 	fn fetch_price() -> Self::Balance;
 }
 
-/// A trait to provide relative price for two currencies
-pub trait SerpTesPriceProvider<CurrencyId, Price> {
-	fn get_price(base: CurrencyId, quote: CurrencyId) -> Option<Price>;
+/// Per-currency price oracle interface. `fetch_price` returns the price of
+/// `currency_id`, or `None` if it is not available.
+pub trait FetchCurrencyPrice<CurrencyId, Price> {
+	/// Fetch the current price of `currency_id`.
+	fn fetch_price(currency_id: CurrencyId) -> Option<Price>;
 }
+
+/// The price provider consumed by `SerpTes`, kept as an alias of
+/// `PriceProvider` so any `PriceProvider` implementation can drive the SERP.
+pub use crate::price::PriceProvider as SerpTesPriceProvider;
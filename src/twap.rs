@@ -0,0 +1,371 @@
+use crate::{DataProviderExtended, TimestampedValue};
+use codec::{Decode, Encode};
+use frame_support::traits::{Get, Time};
+use sp_runtime::{
+	traits::{CheckedDiv, CheckedSub},
+	FixedPointNumber, FixedU128, RuntimeDebug,
+};
+use sp_std::{convert::TryInto, marker::PhantomData, prelude::*};
+
+/// A cumulative price observation.
+#[derive(Encode, Decode, Clone, Copy, Default, Eq, PartialEq, RuntimeDebug)]
+pub struct TwapObservation<Moment> {
+	/// When the observation was taken.
+	pub timestamp: Moment,
+	/// Sum of price × elapsed time up to `timestamp`, in `FixedU128` inner
+	/// units. Wraps on overflow, only differences are meaningful, so averages
+	/// are only correct over windows whose sum stays below `u128::MAX`.
+	pub cumulative: u128,
+	/// The price from `timestamp` on, in `FixedU128` inner units.
+	pub price: u128,
+}
+
+/// A bounded ring buffer of observations of one key.
+#[derive(Encode, Decode, Clone, Default, Eq, PartialEq, RuntimeDebug)]
+pub struct TwapObservations<Moment> {
+	/// The observations, oldest at `next` once the buffer is full.
+	pub observations: Vec<TwapObservation<Moment>>,
+	/// The slot overwritten by the next observation once the buffer is full.
+	pub next: u32,
+}
+
+impl<Moment: Copy + PartialOrd + TryInto<u128>> TwapObservations<Moment> {
+	/// Append `observation`, overwriting the oldest one if `capacity` is
+	/// reached. The oldest observations are dropped first if `capacity` is
+	/// below the number of observations.
+	pub fn push(&mut self, observation: TwapObservation<Moment>, capacity: u32) {
+		if capacity == 0 {
+			return;
+		}
+		if self.observations.len() > capacity as usize {
+			// the capacity shrank
+			let mut observations: Vec<_> = self.iter().copied().collect();
+			let excess = observations.len() - capacity as usize;
+			observations.drain(..excess);
+			self.observations = observations;
+			self.next = 0;
+		}
+		if self.observations.len() < capacity as usize {
+			let next = self.next();
+			if next == 0 || next == self.observations.len() {
+				self.observations.push(observation);
+				self.next = 0;
+			} else {
+				// the capacity grew after the buffer wrapped
+				self.observations.insert(next, observation);
+				self.next = next as u32 + 1;
+			}
+		} else {
+			let next = self.next as usize % self.observations.len();
+			self.observations[next] = observation;
+			self.next = ((next + 1) % self.observations.len()) as u32;
+		}
+	}
+
+	/// `next` as an index, clamped to the observations as it may be decoded
+	/// out of range.
+	fn next(&self) -> usize {
+		(self.next as usize).min(self.observations.len())
+	}
+
+	/// The observations from oldest to newest.
+	pub fn iter(&self) -> impl Iterator<Item = &TwapObservation<Moment>> {
+		let (newer, older) = self.observations.split_at(self.next());
+		older.iter().chain(newer.iter())
+	}
+
+	/// The newest observation.
+	pub fn latest(&self) -> Option<&TwapObservation<Moment>> {
+		self.iter().last()
+	}
+
+	/// The cumulative price at `at`, extending the newest observation taken
+	/// no later than `at`. `None` if every observation is newer, or if price
+	/// × elapsed time overflows.
+	pub fn cumulative_at(&self, at: Moment) -> Option<u128> {
+		let observation = self.iter().filter(|o| o.timestamp <= at).last()?;
+		let elapsed = elapsed(observation.timestamp, at)?;
+		Some(observation.cumulative.wrapping_add(observation.price.checked_mul(elapsed)?))
+	}
+}
+
+fn elapsed<Moment: TryInto<u128>>(from: Moment, to: Moment) -> Option<u128> {
+	let from: u128 = from.try_into().ok()?;
+	let to: u128 = to.try_into().ok()?;
+	to.checked_sub(from)
+}
+
+/// Storage of the observations of every key.
+pub trait TwapStore<Key, Moment> {
+	/// The observations of `key`.
+	fn get(key: &Key) -> TwapObservations<Moment>;
+	/// Replace the observations of `key`.
+	fn insert(key: &Key, observations: TwapObservations<Moment>);
+}
+
+/// A trait to provide time-weighted average relative price for two currencies
+pub trait TwapPriceProvider<CurrencyId, Moment, Price> {
+	/// The average price of `base` in `quote` over the last `window`.
+	fn get_twap(base: CurrencyId, quote: CurrencyId, window: Moment) -> Option<Price>;
+}
+
+/// A `TwapPriceProvider` implementation based on snapshots of price data from
+/// a `DataProviderExtended`.
+///
+/// `record` must be called regularly, typically on every block, to take a
+/// snapshot of a key. At most `MaxObservations` snapshots are kept per key,
+/// so `get_twap` returns `None` for windows reaching further back than the
+/// oldest one.
+pub struct DefaultTwapPriceProvider<CurrencyId, Source, Store, Now, MaxObservations>(
+	PhantomData<(CurrencyId, Source, Store, Now, MaxObservations)>,
+);
+
+impl<CurrencyId, Source, Store, Now, MaxObservations>
+	DefaultTwapPriceProvider<CurrencyId, Source, Store, Now, MaxObservations>
+where
+	Source: DataProviderExtended<CurrencyId, TimestampedValue<FixedU128, Now::Moment>>,
+	Store: TwapStore<CurrencyId, Now::Moment>,
+	Now: Time,
+	MaxObservations: Get<u32>,
+{
+	/// Take a snapshot of `key` from `Source`, if it is newer than the last
+	/// one and price × elapsed time since the last one does not overflow.
+	pub fn record(key: &CurrencyId) {
+		let snapshot = match Source::get_no_op(key) {
+			Some(snapshot) => snapshot,
+			None => return,
+		};
+		let mut observations = Store::get(key);
+		let cumulative = match observations.latest() {
+			Some(latest) if snapshot.timestamp <= latest.timestamp => return,
+			Some(latest) => match elapsed(latest.timestamp, snapshot.timestamp)
+				.and_then(|elapsed| latest.price.checked_mul(elapsed))
+			{
+				Some(increase) => latest.cumulative.wrapping_add(increase),
+				None => return,
+			},
+			None => 0,
+		};
+		observations.push(
+			TwapObservation {
+				timestamp: snapshot.timestamp,
+				cumulative,
+				price: snapshot.value.into_inner(),
+			},
+			MaxObservations::get(),
+		);
+		Store::insert(key, observations);
+	}
+
+	fn average(key: &CurrencyId, start: Now::Moment, end: Now::Moment, window: u128) -> Option<u128> {
+		let observations = Store::get(key);
+		let sum = observations
+			.cumulative_at(end)?
+			.wrapping_sub(observations.cumulative_at(start)?);
+		Some(sum / window)
+	}
+}
+
+impl<CurrencyId, Source, Store, Now, MaxObservations> TwapPriceProvider<CurrencyId, Now::Moment, FixedU128>
+	for DefaultTwapPriceProvider<CurrencyId, Source, Store, Now, MaxObservations>
+where
+	Source: DataProviderExtended<CurrencyId, TimestampedValue<FixedU128, Now::Moment>>,
+	Store: TwapStore<CurrencyId, Now::Moment>,
+	Now: Time,
+	MaxObservations: Get<u32>,
+{
+	fn get_twap(base: CurrencyId, quote: CurrencyId, window: Now::Moment) -> Option<FixedU128> {
+		let end = Now::now();
+		let start = end.checked_sub(&window)?;
+		let length = TryInto::<u128>::try_into(window).ok()?;
+		if length == 0 {
+			return None;
+		}
+
+		let base_price = FixedU128::from_inner(Self::average(&base, start, end, length)?);
+		let quote_price = FixedU128::from_inner(Self::average(&quote, start, end, length)?);
+		base_price.checked_div(&quote_price)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use frame_support::parameter_types;
+	use std::{cell::RefCell, collections::BTreeMap};
+
+	thread_local! {
+		static NOW: RefCell<u64> = RefCell::new(0);
+		static PRICES: RefCell<BTreeMap<u32, TimestampedValue<FixedU128, u64>>> = RefCell::new(BTreeMap::new());
+		static OBSERVATIONS: RefCell<BTreeMap<u32, TwapObservations<u64>>> = RefCell::new(BTreeMap::new());
+	}
+
+	pub struct MockTime;
+	impl Time for MockTime {
+		type Moment = u64;
+
+		fn now() -> u64 {
+			NOW.with(|v| *v.borrow())
+		}
+	}
+
+	pub struct MockDataProvider;
+	impl DataProviderExtended<u32, TimestampedValue<FixedU128, u64>> for MockDataProvider {
+		fn get_no_op(key: &u32) -> Option<TimestampedValue<FixedU128, u64>> {
+			PRICES.with(|v| v.borrow().get(key).copied())
+		}
+
+		fn get_all_values() -> Vec<(u32, Option<TimestampedValue<FixedU128, u64>>)> {
+			PRICES.with(|v| v.borrow().iter().map(|(k, v)| (*k, Some(*v))).collect())
+		}
+	}
+
+	pub struct MockStore;
+	impl TwapStore<u32, u64> for MockStore {
+		fn get(key: &u32) -> TwapObservations<u64> {
+			OBSERVATIONS.with(|v| v.borrow().get(key).cloned().unwrap_or_default())
+		}
+
+		fn insert(key: &u32, observations: TwapObservations<u64>) {
+			OBSERVATIONS.with(|v| v.borrow_mut().insert(*key, observations));
+		}
+	}
+
+	parameter_types! {
+		pub const MaxObservations: u32 = 3;
+	}
+
+	type Twap = DefaultTwapPriceProvider<u32, MockDataProvider, MockStore, MockTime, MaxObservations>;
+
+	fn price(n: u128) -> FixedU128 {
+		FixedU128::saturating_from_integer(n)
+	}
+
+	/// Set the price of `key` at `now` and record it.
+	fn feed(now: u64, key: u32, value: FixedU128) {
+		NOW.with(|v| *v.borrow_mut() = now);
+		PRICES.with(|v| {
+			v.borrow_mut().insert(key, TimestampedValue { value, timestamp: now });
+		});
+		Twap::record(&key);
+	}
+
+	fn set_now(now: u64) {
+		NOW.with(|v| *v.borrow_mut() = now);
+	}
+
+	#[test]
+	fn ring_buffer_should_keep_newest() {
+		let mut observations = TwapObservations::<u64>::default();
+		for timestamp in 0..5u64 {
+			observations.push(
+				TwapObservation {
+					timestamp,
+					cumulative: 0,
+					price: 0,
+				},
+				3,
+			);
+		}
+		let timestamps: Vec<u64> = observations.iter().map(|o| o.timestamp).collect();
+		assert_eq!(timestamps, vec![2, 3, 4]);
+		assert_eq!(observations.latest().map(|o| o.timestamp), Some(4));
+		assert_eq!(observations.observations.len(), 3);
+	}
+
+	#[test]
+	fn ring_buffer_should_shrink_to_capacity() {
+		let observation = |timestamp| TwapObservation {
+			timestamp,
+			cumulative: 0,
+			price: 0,
+		};
+		let mut observations = TwapObservations::<u64>::default();
+		for timestamp in 0..6u64 {
+			observations.push(observation(timestamp), 4);
+		}
+		assert_eq!(observations.next, 2);
+
+		observations.push(observation(6), 2);
+		let timestamps: Vec<u64> = observations.iter().map(|o| o.timestamp).collect();
+		assert_eq!(timestamps, vec![5, 6]);
+		assert_eq!(observations.observations.len(), 2);
+
+		observations.push(observation(7), 2);
+		let timestamps: Vec<u64> = observations.iter().map(|o| o.timestamp).collect();
+		assert_eq!(timestamps, vec![6, 7]);
+	}
+
+	#[test]
+	fn ring_buffer_should_clamp_out_of_range_next() {
+		let observation = |timestamp| TwapObservation {
+			timestamp,
+			cumulative: 0,
+			price: 0,
+		};
+		let mut observations = TwapObservations::<u64> {
+			observations: vec![observation(0), observation(1)],
+			next: 5,
+		};
+		let timestamps: Vec<u64> = observations.iter().map(|o| o.timestamp).collect();
+		assert_eq!(timestamps, vec![0, 1]);
+
+		observations.push(observation(2), 3);
+		let timestamps: Vec<u64> = observations.iter().map(|o| o.timestamp).collect();
+		assert_eq!(timestamps, vec![0, 1, 2]);
+
+		observations.push(observation(3), 3);
+		let timestamps: Vec<u64> = observations.iter().map(|o| o.timestamp).collect();
+		assert_eq!(timestamps, vec![1, 2, 3]);
+	}
+
+	#[test]
+	fn price_times_elapsed_time_should_not_wrap() {
+		feed(0, 1, FixedU128::from_inner(u128::max_value()));
+		feed(0, 2, price(1));
+		feed(10, 1, price(1));
+		assert_eq!(MockStore::get(&1).observations.len(), 1);
+
+		set_now(10);
+		assert_eq!(Twap::get_twap(1, 2, 10), None);
+	}
+
+	#[test]
+	fn get_twap_should_weight_by_duration() {
+		feed(0, 1, price(1));
+		feed(0, 2, price(1));
+		feed(10, 1, price(3));
+		set_now(20);
+
+		assert_eq!(Twap::get_twap(1, 2, 20), Some(price(2)));
+		assert_eq!(Twap::get_twap(1, 2, 10), Some(price(3)));
+		assert_eq!(Twap::get_twap(2, 1, 5), Some(FixedU128::saturating_from_rational(1, 3)));
+		assert_eq!(Twap::get_twap(1, 2, 0), None);
+		assert_eq!(Twap::get_twap(1, 2, 21), None);
+		assert_eq!(Twap::get_twap(1, 3, 10), None);
+	}
+
+	#[test]
+	fn record_should_ignore_old_snapshots() {
+		feed(0, 1, price(1));
+		feed(10, 1, price(3));
+		Twap::record(&1);
+		assert_eq!(MockStore::get(&1).observations.len(), 2);
+
+		feed(5, 1, price(100));
+		assert_eq!(MockStore::get(&1).observations.len(), 2);
+	}
+
+	#[test]
+	fn get_twap_should_not_reach_past_oldest_observation() {
+		feed(0, 1, price(1));
+		feed(0, 2, price(1));
+		feed(10, 1, price(2));
+		feed(20, 1, price(3));
+		feed(30, 1, price(4));
+		set_now(40);
+
+		assert_eq!(Twap::get_twap(1, 2, 40), None);
+		assert_eq!(Twap::get_twap(1, 2, 30), Some(price(3)));
+	}
+}